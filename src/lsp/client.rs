@@ -24,7 +24,7 @@
 //! client.shutdown().await?;
 //! ```
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::ops::ControlFlow;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
@@ -35,20 +35,32 @@ use async_lsp::panic::CatchUnwindLayer;
 use async_lsp::router::Router;
 use async_lsp::tracing::TracingLayer;
 use async_lsp::{LanguageServer, ServerSocket};
+use lsp_types::notification::Notification as _;
+use lsp_types::request::Request as _;
 use lsp_types::{
     CallHierarchyIncomingCall, CallHierarchyIncomingCallsParams, CallHierarchyItem,
     CallHierarchyOutgoingCall, CallHierarchyOutgoingCallsParams, CallHierarchyPrepareParams,
-    ClientCapabilities, ClientInfo, CompletionClientCapabilities, CompletionItemCapability,
+    ClientCapabilities, ClientInfo, CompletionClientCapabilities, CompletionItem,
+    CompletionItemCapability, CompletionItemCapabilityResolveSupport, CompletionParams,
+    CompletionResponse, Diagnostic, DiagnosticClientCapabilities, DiagnosticOptions,
+    DiagnosticServerCapabilities, DiagnosticWorkspaceClientCapabilities,
     DidChangeTextDocumentParams, DidChangeWatchedFilesClientCapabilities,
-    DidCloseTextDocumentParams, DidOpenTextDocumentParams, DocumentSymbolClientCapabilities,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, DocumentDiagnosticParams,
+    DocumentDiagnosticReport, DocumentDiagnosticReportKind, DocumentDiagnosticReportResult,
+    DocumentHighlight, DocumentHighlightParams, DocumentSymbolClientCapabilities,
     DocumentSymbolParams, DocumentSymbolResponse, DynamicRegistrationClientCapabilities,
     GotoCapability, GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverClientCapabilities,
-    HoverParams, InitializeParams, InitializedParams, Location, MarkupKind, PartialResultParams,
-    ReferenceContext, ReferenceParams, ServerCapabilities, SymbolInformation,
+    HoverParams, InitializeParams, InitializedParams, InlayHint, InlayHintClientCapabilities,
+    InlayHintParams, InlayHintResolveClientCapabilities, Location, MarkupKind, PartialResultParams,
+    PreviousResultId, ProgressParams, ProgressParamsValue, ProgressToken, PublishDiagnosticsParams,
+    Range, ReferenceContext, ReferenceParams, ServerCapabilities, SymbolInformation,
     TextDocumentClientCapabilities, TextDocumentContentChangeEvent, TextDocumentIdentifier,
     TextDocumentItem, TextDocumentPositionParams, TextDocumentSyncClientCapabilities, TraceValue,
-    Url, VersionedTextDocumentIdentifier, WindowClientCapabilities, WorkDoneProgressParams,
-    WorkspaceClientCapabilities, WorkspaceEditClientCapabilities, WorkspaceFolder,
+    TypeHierarchyItem, TypeHierarchyPrepareParams, TypeHierarchySubtypesParams,
+    TypeHierarchySupertypesParams, Url, VersionedTextDocumentIdentifier, WindowClientCapabilities,
+    WorkDoneProgress, WorkDoneProgressParams, WorkspaceClientCapabilities,
+    WorkspaceDiagnosticParams, WorkspaceDiagnosticReportResult, WorkspaceDocumentDiagnosticReport,
+    WorkspaceEdit, WorkspaceEditClientCapabilities, WorkspaceFolder,
     WorkspaceSymbolClientCapabilities, WorkspaceSymbolParams, WorkspaceSymbolResponse,
     notification, request,
 };
@@ -58,23 +70,163 @@ use tower::ServiceBuilder;
 use crate::error::LspError;
 
 use super::LspResult;
+use super::ext::{
+    AnalyzerStatus, AnalyzerStatusParams, ChildModules, CrateInfo, ExpandMacro, ExpandMacroParams,
+    ExpandedMacro, ExternalDocs, ExternalDocsPair, FLYCHECK_PROGRESS_TOKEN, FetchDependencyList,
+    FetchDependencyListParams, MemoryUsage, ParentModule, RebuildProcMacros, RelatedTests,
+    ReloadWorkspace, RunFlycheck, RunFlycheckParams, Runnable, Runnables, RunnablesParams, Ssr,
+    SsrParams, TestInfo, ViewCrateGraph, ViewCrateGraphParams, ViewHir, ViewMir, ViewSyntaxTree,
+    ViewSyntaxTreeParams,
+};
 use super::types::{path_to_url, to_lsp_position};
 
+/// Converts an error from a language server request into an `LspError`.
+///
+/// A "method not found" response means the server does not implement the
+/// request at all, which is reported as `CapabilityNotSupported` so callers
+/// can tell it apart from a failed request.
+fn request_error(method: &str, error: async_lsp::Error) -> LspError {
+    match error {
+        async_lsp::Error::Response(response)
+            if response.code == async_lsp::ErrorCode::METHOD_NOT_FOUND =>
+        {
+            LspError::CapabilityNotSupported(method.to_string())
+        }
+        error => LspError::RequestFailed(format!("{method} failed: {error:?}")),
+    }
+}
+
 /// State for handling LSP client notifications.
 ///
 /// This struct maintains the state needed to handle notifications
 /// from the language server.
 #[derive(Debug, Clone)]
 struct ClientState {
-    // Track diagnostics, progress, etc. if needed in the future
+    /// Events recorded for the client, shared with `LspClient`.
+    events: Arc<ServerEvents>,
 }
 
 impl ClientState {
+    fn new(events: Arc<ServerEvents>) -> Self {
+        Self { events }
+    }
+}
+
+/// How long to wait for a requested flycheck run to begin.
+///
+/// No run begins when flycheck is disabled (`rust-analyzer.checkOnSave: false`).
+const FLYCHECK_START_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// How long diagnostics must stay unchanged after a flycheck run before they count as
+/// final; rust-analyzer may publish the last of them after the run's end.
+const FLYCHECK_SETTLE_TIME: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Diagnostics and progress received from the language server.
+#[derive(Debug)]
+struct ServerEvents {
+    /// Latest diagnostics published for each document.
+    diagnostics: std::sync::Mutex<HashMap<Url, Vec<Diagnostic>>>,
+    /// Number of diagnostics notifications received, for waiting until they settle.
+    published: tokio::sync::watch::Sender<u64>,
+    /// Progress of flycheck runs, for waiting until a check finishes.
+    flycheck: tokio::sync::watch::Sender<FlycheckStatus>,
+}
+
+impl ServerEvents {
     fn new() -> Self {
-        Self {}
+        Self {
+            diagnostics: std::sync::Mutex::new(HashMap::new()),
+            published: tokio::sync::watch::channel(0).0,
+            flycheck: tokio::sync::watch::channel(FlycheckStatus::default()).0,
+        }
+    }
+
+    /// Records a `$/progress` notification, tracking flycheck begin and end.
+    fn progress(&self, params: ProgressParams) {
+        let ProgressToken::String(token) = params.token else {
+            return;
+        };
+        if !token.starts_with(FLYCHECK_PROGRESS_TOKEN) {
+            return;
+        }
+
+        let ProgressParamsValue::WorkDone(progress) = params.value;
+        self.flycheck.send_modify(|status| match progress {
+            WorkDoneProgress::Begin(_) => {
+                status.started += 1;
+                status.active.insert(token);
+            }
+            WorkDoneProgress::End(_) => {
+                status.active.remove(&token);
+            }
+            WorkDoneProgress::Report(_) => {}
+        });
+    }
+
+    /// Records a `textDocument/publishDiagnostics` notification.
+    fn publish_diagnostics(&self, params: PublishDiagnosticsParams) {
+        let mut diagnostics = self
+            .diagnostics
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if params.diagnostics.is_empty() {
+            diagnostics.remove(&params.uri);
+        } else {
+            diagnostics.insert(params.uri, params.diagnostics);
+        }
+        self.published.send_modify(|count| *count += 1);
+    }
+}
+
+/// Diagnostics pulled for a document, kept for result-id based incremental updates.
+#[derive(Debug, Clone, Default)]
+struct PulledDiagnostics {
+    /// Result id of the last report, sent back so the server can answer "unchanged".
+    result_id: Option<String>,
+    /// Diagnostics from the last full report.
+    items: Vec<Diagnostic>,
+}
+
+/// Applies a full or unchanged diagnostic report for a document to the pull cache.
+fn apply_diagnostic_report(
+    cache: &mut HashMap<Url, PulledDiagnostics>,
+    uri: Url,
+    report: DocumentDiagnosticReportKind,
+) {
+    match report {
+        DocumentDiagnosticReportKind::Full(report) => {
+            cache.insert(
+                uri,
+                PulledDiagnostics {
+                    result_id: report.result_id,
+                    items: report.items,
+                },
+            );
+        }
+        DocumentDiagnosticReportKind::Unchanged(report) => {
+            cache.entry(uri).or_default().result_id = Some(report.result_id);
+        }
+    }
+}
+
+/// Adds the diagnostics from `extra` that `diagnostics` does not already contain.
+fn merge_diagnostics(diagnostics: &mut Vec<Diagnostic>, extra: Vec<Diagnostic>) {
+    for diagnostic in extra {
+        if !diagnostics.contains(&diagnostic) {
+            diagnostics.push(diagnostic);
+        }
     }
 }
 
+/// Progress of flycheck runs.
+#[derive(Debug, Clone, Default)]
+struct FlycheckStatus {
+    /// Number of flycheck runs that have begun.
+    started: u64,
+    /// Progress tokens of the flycheck runs in progress.
+    active: HashSet<String>,
+}
+
 /// Configuration for building an LSP client.
 #[derive(Debug, Clone)]
 pub struct LspClientConfig {
@@ -187,21 +339,29 @@ impl LspClientBuilder {
             .ok_or_else(|| LspError::ServerStartFailed("failed to capture stdin".to_string()))?;
 
         // Create the mainloop with router for notifications
+        let events = Arc::new(ServerEvents::new());
+        let router_events = Arc::clone(&events);
         let (mainloop, server) = async_lsp::MainLoop::new_client(|_client| {
-            let mut router = Router::new(ClientState::new());
+            let mut router = Router::new(ClientState::new(router_events));
 
             // Handle progress notifications
-            router.notification::<notification::Progress>(|_this, _prog| {
-                // Can log or track progress here if needed
+            router.notification::<notification::Progress>(|this, params| {
+                this.events.progress(params);
                 ControlFlow::Continue(())
             });
 
             // Handle publish diagnostics notifications
-            router.notification::<notification::PublishDiagnostics>(|_this, _diag| {
-                // Can collect diagnostics here if needed
+            router.notification::<notification::PublishDiagnostics>(|this, params| {
+                this.events.publish_diagnostics(params);
                 ControlFlow::Continue(())
             });
 
+            // Accept progress tokens and refresh requests so the server keeps reporting
+            router.request::<request::WorkDoneProgressCreate, _>(|_this, _params| async { Ok(()) });
+            router.request::<request::WorkspaceDiagnosticRefresh, _>(|_this, _params| async {
+                Ok(())
+            });
+
             // Build the service with layers
             ServiceBuilder::new()
                 .layer(TracingLayer::default())
@@ -255,6 +415,9 @@ impl LspClientBuilder {
                     execute_command: Some(DynamicRegistrationClientCapabilities {
                         dynamic_registration: Some(false),
                     }),
+                    diagnostic: Some(DiagnosticWorkspaceClientCapabilities {
+                        refresh_support: Some(true),
+                    }),
                     ..Default::default()
                 }),
                 text_document: Some(TextDocumentClientCapabilities {
@@ -268,6 +431,14 @@ impl LspClientBuilder {
                         dynamic_registration: Some(false),
                         completion_item: Some(CompletionItemCapability {
                             snippet_support: Some(false),
+                            label_details_support: Some(true),
+                            resolve_support: Some(CompletionItemCapabilityResolveSupport {
+                                properties: vec![
+                                    "documentation".to_string(),
+                                    "detail".to_string(),
+                                    "additionalTextEdits".to_string(),
+                                ],
+                            }),
                             ..Default::default()
                         }),
                         ..Default::default()
@@ -283,6 +454,9 @@ impl LspClientBuilder {
                     references: Some(DynamicRegistrationClientCapabilities {
                         dynamic_registration: Some(false),
                     }),
+                    document_highlight: Some(DynamicRegistrationClientCapabilities {
+                        dynamic_registration: Some(false),
+                    }),
                     document_symbol: Some(DocumentSymbolClientCapabilities {
                         dynamic_registration: Some(false),
                         hierarchical_document_symbol_support: Some(true),
@@ -296,16 +470,39 @@ impl LspClientBuilder {
                         dynamic_registration: Some(false),
                         link_support: Some(false),
                     }),
+                    declaration: Some(GotoCapability {
+                        dynamic_registration: Some(false),
+                        link_support: Some(true),
+                    }),
                     call_hierarchy: Some(DynamicRegistrationClientCapabilities {
                         dynamic_registration: Some(false),
                     }),
+                    type_hierarchy: Some(DynamicRegistrationClientCapabilities {
+                        dynamic_registration: Some(false),
+                    }),
+                    diagnostic: Some(DiagnosticClientCapabilities {
+                        dynamic_registration: Some(false),
+                        related_document_support: Some(true),
+                    }),
+                    inlay_hint: Some(InlayHintClientCapabilities {
+                        dynamic_registration: Some(false),
+                        resolve_support: Some(InlayHintResolveClientCapabilities {
+                            properties: vec![
+                                "tooltip".to_string(),
+                                "textEdits".to_string(),
+                                "label.tooltip".to_string(),
+                                "label.location".to_string(),
+                            ],
+                        }),
+                    }),
                     ..Default::default()
                 }),
                 window: Some(WindowClientCapabilities {
                     work_done_progress: Some(true),
                     ..Default::default()
                 }),
-                experimental: Some(true.into()),
+                // Ask for `cargo doc` paths next to web URLs in `experimental/externalDocs`
+                experimental: Some(serde_json::json!({ "localDocs": true })),
                 ..Default::default()
             },
             trace: Some(TraceValue::Off),
@@ -347,6 +544,8 @@ impl LspClientBuilder {
             _mainloop_handle: mainloop_handle,
             capabilities,
             open_documents: Arc::new(Mutex::new(HashSet::new())),
+            events,
+            pulled_diagnostics: Arc::new(Mutex::new(HashMap::new())),
             _child_process: Arc::new(Mutex::new(child)),
         })
     }
@@ -368,6 +567,10 @@ pub struct LspClient {
     capabilities: Arc<ServerCapabilities>,
     /// Set of currently open documents.
     open_documents: Arc<Mutex<HashSet<Url>>>,
+    /// Diagnostics and progress received from the server.
+    events: Arc<ServerEvents>,
+    /// Diagnostics pulled from the server, per document.
+    pulled_diagnostics: Arc<Mutex<HashMap<Url, PulledDiagnostics>>>,
     /// The language server process handle (kept alive to prevent kill-on-drop).
     _child_process: Arc<Mutex<async_process::Child>>,
 }
@@ -387,6 +590,11 @@ impl LspClient {
         LspClientBuilder::new()
     }
 
+    /// Returns the capabilities the language server reported during initialization.
+    pub fn capabilities(&self) -> &ServerCapabilities {
+        &self.capabilities
+    }
+
     /// Shuts down the language server gracefully.
     /// ## Errors
     pub async fn shutdown(&self) -> LspResult<()> {
@@ -552,6 +760,40 @@ impl LspClient {
         Ok(result.unwrap_or(GotoDefinitionResponse::Array(vec![])))
     }
 
+    /// Gets the declaration location(s) for the symbol at the given position.
+    ///
+    /// For Rust this resolves an impl method to its trait method declaration; for C/C++
+    /// servers it resolves to the header declaration rather than the definition.
+    /// ## Errors
+    pub async fn goto_declaration(
+        &self,
+        path: &Path,
+        line: u32,
+        column: u32,
+    ) -> LspResult<GotoDefinitionResponse> {
+        let uri = path_to_url(path)?;
+        let position = to_lsp_position(line, column)?;
+
+        let params = request::GotoDeclarationParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let result = tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.declaration(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(request::GotoDeclaration::METHOD, e))?;
+
+        Ok(result.unwrap_or(GotoDefinitionResponse::Array(vec![])))
+    }
+
     /// Finds all references to the symbol at the given position.
     /// ## Errors
     pub async fn find_references(
@@ -612,6 +854,133 @@ impl LspClient {
         Ok(result)
     }
 
+    /// Gets all occurrences of the symbol at the given position within its document.
+    ///
+    /// Each highlight carries a kind telling whether the occurrence reads or writes the symbol.
+    /// ## Errors
+    pub async fn document_highlights(
+        &self,
+        path: &Path,
+        line: u32,
+        column: u32,
+    ) -> LspResult<Vec<DocumentHighlight>> {
+        let uri = path_to_url(path)?;
+        let position = to_lsp_position(line, column)?;
+
+        let params = DocumentHighlightParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let result = tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.document_highlight(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(request::DocumentHighlightRequest::METHOD, e))?;
+
+        Ok(result.unwrap_or_default())
+    }
+
+    /// Gets inlay hints (inferred types, parameter names, chaining hints) for a line range.
+    ///
+    /// Both `start_line` and `end_line` are 1-indexed and inclusive.
+    /// ## Errors
+    pub async fn inlay_hints(
+        &self,
+        path: &Path,
+        start_line: u32,
+        end_line: u32,
+    ) -> LspResult<Vec<InlayHint>> {
+        let uri = path_to_url(path)?;
+        let range = Range {
+            start: to_lsp_position(start_line, 1)?,
+            // End is exclusive, so cover the whole of `end_line`
+            end: to_lsp_position(end_line.saturating_add(1), 1)?,
+        };
+
+        let params = InlayHintParams {
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            text_document: TextDocumentIdentifier { uri },
+            range,
+        };
+
+        let result = tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.inlay_hint(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(request::InlayHintRequest::METHOD, e))?;
+
+        Ok(result.unwrap_or_default())
+    }
+
+    /// Resolves lazily computed properties (tooltips, label locations) of an inlay hint.
+    /// ## Errors
+    pub async fn resolve_inlay_hint(&self, hint: InlayHint) -> LspResult<InlayHint> {
+        tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.inlay_hint_resolve(hint),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(request::InlayHintResolveRequest::METHOD, e))
+    }
+
+    /// Gets completion items available at the given position.
+    /// ## Errors
+    pub async fn completion(
+        &self,
+        path: &Path,
+        line: u32,
+        column: u32,
+    ) -> LspResult<Vec<CompletionItem>> {
+        let uri = path_to_url(path)?;
+        let position = to_lsp_position(line, column)?;
+
+        let params = CompletionParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+            context: None,
+        };
+
+        let result = tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.completion(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(request::Completion::METHOD, e))?;
+
+        match result {
+            Some(CompletionResponse::Array(items)) => Ok(items),
+            Some(CompletionResponse::List(list)) => Ok(list.items),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Resolves lazily computed properties (documentation, imports) of a completion item.
+    /// ## Errors
+    pub async fn resolve_completion_item(&self, item: CompletionItem) -> LspResult<CompletionItem> {
+        tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.completion_item_resolve(item),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(request::ResolveCompletionItem::METHOD, e))
+    }
+
     /// Gets all symbols in a document.
     /// ## Errors
     pub async fn document_symbols(&self, path: &Path) -> LspResult<DocumentSymbolResponse> {
@@ -819,6 +1188,709 @@ impl LspClient {
 
         Ok(result.unwrap_or(GotoDefinitionResponse::Array(vec![])))
     }
+
+    /// Prepares type hierarchy items for the symbol at the given position.
+    ///
+    /// The returned items are the roots for `supertypes` and `subtypes` requests.
+    /// ## Errors
+    pub async fn prepare_type_hierarchy(
+        &self,
+        path: &Path,
+        line: u32,
+        column: u32,
+    ) -> LspResult<Vec<TypeHierarchyItem>> {
+        let uri = path_to_url(path)?;
+        let position = to_lsp_position(line, column)?;
+
+        let params = TypeHierarchyPrepareParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+
+        let result = tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.prepare_type_hierarchy(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(request::TypeHierarchyPrepare::METHOD, e))?;
+
+        Ok(result.unwrap_or_default())
+    }
+
+    /// Gets the direct supertypes of a type hierarchy item.
+    /// ## Errors
+    pub async fn supertypes(&self, item: TypeHierarchyItem) -> LspResult<Vec<TypeHierarchyItem>> {
+        let params = TypeHierarchySupertypesParams {
+            item,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let result = tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.supertypes(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(request::TypeHierarchySupertypes::METHOD, e))?;
+
+        Ok(result.unwrap_or_default())
+    }
+
+    /// Gets the direct subtypes of a type hierarchy item.
+    /// ## Errors
+    pub async fn subtypes(&self, item: TypeHierarchyItem) -> LspResult<Vec<TypeHierarchyItem>> {
+        let params = TypeHierarchySubtypesParams {
+            item,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let result = tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.subtypes(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(request::TypeHierarchySubtypes::METHOD, e))?;
+
+        Ok(result.unwrap_or_default())
+    }
+
+    // rust-analyzer extensions
+
+    /// Recursively expands the macro call at the given position.
+    ///
+    /// Uses rust-analyzer's `rust-analyzer/expandMacro` extension. Returns `None` when
+    /// there is no macro call at the position.
+    /// ## Errors
+    pub async fn expand_macro(
+        &self,
+        path: &Path,
+        line: u32,
+        column: u32,
+    ) -> LspResult<Option<ExpandedMacro>> {
+        let uri = path_to_url(path)?;
+        let position = to_lsp_position(line, column)?;
+
+        let params = ExpandMacroParams {
+            text_document: TextDocumentIdentifier { uri },
+            position,
+        };
+
+        tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.request::<ExpandMacro>(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(ExpandMacro::METHOD, e))
+    }
+
+    /// Finds the `mod` declaration of the module containing the given position.
+    ///
+    /// Uses rust-analyzer's `experimental/parentModule` extension. For a crate root the
+    /// response links to the package's `Cargo.toml`.
+    /// ## Errors
+    pub async fn parent_module(
+        &self,
+        path: &Path,
+        line: u32,
+        column: u32,
+    ) -> LspResult<Option<GotoDefinitionResponse>> {
+        let uri = path_to_url(path)?;
+        let position = to_lsp_position(line, column)?;
+
+        let params = TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri },
+            position,
+        };
+
+        tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.request::<ParentModule>(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(ParentModule::METHOD, e))
+    }
+
+    /// Finds the `mod` declarations of the submodules of the module at the given position.
+    ///
+    /// Uses rust-analyzer's `experimental/childModules` extension. On a `mod` declaration
+    /// the submodules of the declared module are returned.
+    /// ## Errors
+    pub async fn child_modules(
+        &self,
+        path: &Path,
+        line: u32,
+        column: u32,
+    ) -> LspResult<Option<GotoDefinitionResponse>> {
+        let uri = path_to_url(path)?;
+        let position = to_lsp_position(line, column)?;
+
+        let params = TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri },
+            position,
+        };
+
+        tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.request::<ChildModules>(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(ChildModules::METHOD, e))
+    }
+
+    /// Computes the edit for a structural search and replace rule without applying it.
+    ///
+    /// Uses rust-analyzer's `experimental/ssr` extension. Paths in the rule are resolved
+    /// in the scope at the given position. Matches are restricted to `selections` in the
+    /// document, or searched across the whole workspace when `selections` is empty.
+    /// ## Errors
+    pub async fn ssr(
+        &self,
+        rule: &str,
+        path: &Path,
+        line: u32,
+        column: u32,
+        selections: Vec<Range>,
+    ) -> LspResult<WorkspaceEdit> {
+        let uri = path_to_url(path)?;
+        let position = to_lsp_position(line, column)?;
+
+        let params = SsrParams {
+            query: rule.to_string(),
+            parse_only: false,
+            position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position,
+            },
+            selections,
+        };
+
+        tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.request::<Ssr>(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(Ssr::METHOD, e))
+    }
+
+    /// Asks the server to reload the workspace, e.g. after a `Cargo.toml` change.
+    ///
+    /// Uses rust-analyzer's `rust-analyzer/reloadWorkspace` extension. The server
+    /// re-indexes in the background after the request returns.
+    /// ## Errors
+    pub async fn reload_workspace(&self) -> LspResult<()> {
+        tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.request::<ReloadWorkspace>(()),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(ReloadWorkspace::METHOD, e))
+    }
+
+    /// Asks the server to re-run build scripts and rebuild proc macros.
+    ///
+    /// Uses rust-analyzer's `rust-analyzer/rebuildProcMacros` extension.
+    /// ## Errors
+    pub async fn rebuild_proc_macros(&self) -> LspResult<()> {
+        tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.request::<RebuildProcMacros>(()),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(RebuildProcMacros::METHOD, e))
+    }
+
+    /// Gets a plain-text report of the server's state, optionally for one document's crates.
+    ///
+    /// Uses rust-analyzer's `rust-analyzer/analyzerStatus` extension.
+    /// ## Errors
+    pub async fn analyzer_status(&self, path: Option<&Path>) -> LspResult<String> {
+        let text_document = path
+            .map(|path| path_to_url(path).map(|uri| TextDocumentIdentifier { uri }))
+            .transpose()?;
+
+        let params = AnalyzerStatusParams { text_document };
+
+        tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.request::<AnalyzerStatus>(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(AnalyzerStatus::METHOD, e))
+    }
+
+    /// Gets a plain-text report of the server's memory usage.
+    ///
+    /// Uses rust-analyzer's `rust-analyzer/memoryUsage` extension, which fails unless the
+    /// server was built with memory profiling enabled.
+    /// ## Errors
+    pub async fn memory_usage(&self) -> LspResult<String> {
+        tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.request::<MemoryUsage>(()),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(MemoryUsage::METHOD, e))
+    }
+
+    /// Gets the crate graph in Graphviz DOT format.
+    ///
+    /// Uses rust-analyzer's `rust-analyzer/viewCrateGraph` extension. With `full` unset
+    /// only workspace members are included.
+    /// ## Errors
+    pub async fn view_crate_graph(&self, full: bool) -> LspResult<String> {
+        let params = ViewCrateGraphParams { full };
+
+        tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.request::<ViewCrateGraph>(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(ViewCrateGraph::METHOD, e))
+    }
+
+    /// Lists the dependencies of the workspace with their versions and source roots.
+    ///
+    /// Uses rust-analyzer's `rust-analyzer/fetchDependencyList` extension.
+    /// ## Errors
+    pub async fn fetch_dependency_list(&self) -> LspResult<Vec<CrateInfo>> {
+        let params = FetchDependencyListParams::default();
+
+        let result = tokio::time::timeout(
+            self.config.request_timeout,
+            self.server
+                .lock()
+                .await
+                .request::<FetchDependencyList>(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(FetchDependencyList::METHOD, e))?;
+
+        Ok(result.crates)
+    }
+
+    /// Gets the syntax tree of a document.
+    ///
+    /// Uses rust-analyzer's `rust-analyzer/viewSyntaxTree` extension.
+    /// ## Errors
+    pub async fn view_syntax_tree(&self, path: &Path) -> LspResult<String> {
+        let uri = path_to_url(path)?;
+
+        let params = ViewSyntaxTreeParams {
+            text_document: TextDocumentIdentifier { uri },
+        };
+
+        tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.request::<ViewSyntaxTree>(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(ViewSyntaxTree::METHOD, e))
+    }
+
+    /// Gets the HIR of the function containing the given position.
+    ///
+    /// Uses rust-analyzer's `rust-analyzer/viewHir` extension. Outside a function the
+    /// server returns an explanatory message instead.
+    /// ## Errors
+    pub async fn view_hir(&self, path: &Path, line: u32, column: u32) -> LspResult<String> {
+        let uri = path_to_url(path)?;
+        let position = to_lsp_position(line, column)?;
+
+        let params = TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri },
+            position,
+        };
+
+        tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.request::<ViewHir>(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(ViewHir::METHOD, e))
+    }
+
+    /// Gets the MIR of the function containing the given position.
+    ///
+    /// Uses rust-analyzer's `rust-analyzer/viewMir` extension. Outside a function the
+    /// server returns an explanatory message instead.
+    /// ## Errors
+    pub async fn view_mir(&self, path: &Path, line: u32, column: u32) -> LspResult<String> {
+        let uri = path_to_url(path)?;
+        let position = to_lsp_position(line, column)?;
+
+        let params = TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri },
+            position,
+        };
+
+        tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.request::<ViewMir>(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(ViewMir::METHOD, e))
+    }
+
+    /// Resolves the documentation URLs of the item at the given position.
+    ///
+    /// Uses rust-analyzer's `experimental/externalDocs` extension. The local URL points
+    /// into `target/doc` (or the toolchain docs for `std`) whether or not it was built.
+    /// ## Errors
+    pub async fn external_docs(
+        &self,
+        path: &Path,
+        line: u32,
+        column: u32,
+    ) -> LspResult<ExternalDocsPair> {
+        let uri = path_to_url(path)?;
+        let position = to_lsp_position(line, column)?;
+
+        let params = TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri },
+            position,
+        };
+
+        tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.request::<ExternalDocs>(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map(ExternalDocsPair::from)
+        .map_err(|e| request_error(ExternalDocs::METHOD, e))
+    }
+
+    /// Runs flycheck (`cargo check` or clippy) and waits for it to finish.
+    ///
+    /// Uses rust-analyzer's `rust-analyzer/runFlycheck` notification and waits until a
+    /// flycheck run that began after the notification, and any run alongside it, has
+    /// reported its end, then until no diagnostics were published for
+    /// [`FLYCHECK_SETTLE_TIME`]. With `path` set only the workspace containing it is
+    /// checked. The results are available from [`Self::published_diagnostics`]
+    /// afterwards.
+    /// ## Errors
+    /// Fails if no flycheck run begins within [`FLYCHECK_START_TIMEOUT`], as when
+    /// flycheck is disabled, or if the run does not end within `timeout`.
+    pub async fn run_flycheck(
+        &self,
+        path: Option<&Path>,
+        timeout: std::time::Duration,
+    ) -> LspResult<()> {
+        let text_document = path
+            .map(|path| path_to_url(path).map(|uri| TextDocumentIdentifier { uri }))
+            .transpose()?;
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut flycheck = self.events.flycheck.subscribe();
+        let mut published = self.events.published.subscribe();
+        let started_before = flycheck.borrow().started;
+
+        self.server
+            .lock()
+            .await
+            .notify::<RunFlycheck>(RunFlycheckParams { text_document })
+            .map_err(|e| {
+                LspError::RequestFailed(format!("{} failed: {e:?}", RunFlycheck::METHOD))
+            })?;
+
+        let start_timeout = FLYCHECK_START_TIMEOUT.min(timeout);
+        tokio::time::timeout(
+            start_timeout,
+            flycheck.wait_for(|status| status.started > started_before),
+        )
+        .await
+        .map_err(|_| {
+            LspError::RequestFailed(format!(
+                "no flycheck run began within {start_timeout:?}; is rust-analyzer.checkOnSave enabled?"
+            ))
+        })?
+        .map_err(|e| LspError::RequestFailed(format!("flycheck progress lost: {e}")))?;
+
+        tokio::time::timeout_at(
+            deadline,
+            flycheck.wait_for(|status| status.started > started_before && status.active.is_empty()),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(timeout))?
+        .map_err(|e| LspError::RequestFailed(format!("flycheck progress lost: {e}")))?;
+
+        // Wait for the diagnostics published after the end of the run
+        published.mark_unchanged();
+        let settle = |now: tokio::time::Instant| (now + FLYCHECK_SETTLE_TIME).min(deadline);
+        while let Ok(Ok(())) =
+            tokio::time::timeout_at(settle(tokio::time::Instant::now()), published.changed()).await
+        {
+        }
+
+        Ok(())
+    }
+
+    /// Returns the latest diagnostics the server published, per document.
+    ///
+    /// Includes flycheck (`cargo check`/clippy) results and any diagnostics the
+    /// server pushes itself.
+    pub fn published_diagnostics(&self) -> HashMap<Url, Vec<Diagnostic>> {
+        self.events
+            .diagnostics
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Returns the server's pull diagnostics options, if it supports pull diagnostics.
+    fn diagnostic_options(&self) -> Option<&DiagnosticOptions> {
+        match self.capabilities.diagnostic_provider.as_ref()? {
+            DiagnosticServerCapabilities::Options(options) => Some(options),
+            DiagnosticServerCapabilities::RegistrationOptions(options) => {
+                Some(&options.diagnostic_options)
+            }
+        }
+    }
+
+    /// Pulls the diagnostics for a document (`textDocument/diagnostic`).
+    ///
+    /// The result id of the previous pull is sent along, so an "unchanged" report
+    /// returns the cached diagnostics. Reports for related documents update the cache.
+    /// ## Errors
+    pub async fn document_diagnostics(&self, path: &Path) -> LspResult<Vec<Diagnostic>> {
+        let uri = path_to_url(path)?;
+        self.pull_document_diagnostics(uri).await
+    }
+
+    async fn pull_document_diagnostics(&self, uri: Url) -> LspResult<Vec<Diagnostic>> {
+        let previous_result_id = self
+            .pulled_diagnostics
+            .lock()
+            .await
+            .get(&uri)
+            .and_then(|pulled| pulled.result_id.clone());
+
+        let params = DocumentDiagnosticParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            identifier: self
+                .diagnostic_options()
+                .and_then(|options| options.identifier.clone()),
+            previous_result_id,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let result = tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.document_diagnostic(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(request::DocumentDiagnosticRequest::METHOD, e))?;
+
+        let mut cache = self.pulled_diagnostics.lock().await;
+        let related_documents = match result {
+            DocumentDiagnosticReportResult::Report(DocumentDiagnosticReport::Full(report)) => {
+                let kind =
+                    DocumentDiagnosticReportKind::Full(report.full_document_diagnostic_report);
+                apply_diagnostic_report(&mut cache, uri.clone(), kind);
+                report.related_documents
+            }
+            DocumentDiagnosticReportResult::Report(DocumentDiagnosticReport::Unchanged(report)) => {
+                let kind = DocumentDiagnosticReportKind::Unchanged(
+                    report.unchanged_document_diagnostic_report,
+                );
+                apply_diagnostic_report(&mut cache, uri.clone(), kind);
+                report.related_documents
+            }
+            DocumentDiagnosticReportResult::Partial(partial) => partial.related_documents,
+        };
+        for (related_uri, report) in related_documents.into_iter().flatten() {
+            apply_diagnostic_report(&mut cache, related_uri, report);
+        }
+
+        Ok(cache
+            .get(&uri)
+            .map(|pulled| pulled.items.clone())
+            .unwrap_or_default())
+    }
+
+    /// Pulls the diagnostics for the whole workspace (`workspace/diagnostic`).
+    ///
+    /// The result ids of previous pulls are sent along, so documents reported as
+    /// unchanged keep their cached diagnostics. Returns all cached diagnostics.
+    /// ## Errors
+    pub async fn workspace_diagnostics(&self) -> LspResult<HashMap<Url, Vec<Diagnostic>>> {
+        let previous_result_ids = self
+            .pulled_diagnostics
+            .lock()
+            .await
+            .iter()
+            .filter_map(|(uri, pulled)| {
+                pulled.result_id.clone().map(|value| PreviousResultId {
+                    uri: uri.clone(),
+                    value,
+                })
+            })
+            .collect();
+
+        let params = WorkspaceDiagnosticParams {
+            identifier: self
+                .diagnostic_options()
+                .and_then(|options| options.identifier.clone()),
+            previous_result_ids,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+
+        let result = tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.workspace_diagnostic(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(request::WorkspaceDiagnosticRequest::METHOD, e))?;
+
+        let items = match result {
+            WorkspaceDiagnosticReportResult::Report(report) => report.items,
+            WorkspaceDiagnosticReportResult::Partial(partial) => partial.items,
+        };
+        let mut cache = self.pulled_diagnostics.lock().await;
+        for item in items {
+            match item {
+                WorkspaceDocumentDiagnosticReport::Full(report) => apply_diagnostic_report(
+                    &mut cache,
+                    report.uri,
+                    DocumentDiagnosticReportKind::Full(report.full_document_diagnostic_report),
+                ),
+                WorkspaceDocumentDiagnosticReport::Unchanged(report) => apply_diagnostic_report(
+                    &mut cache,
+                    report.uri,
+                    DocumentDiagnosticReportKind::Unchanged(
+                        report.unchanged_document_diagnostic_report,
+                    ),
+                ),
+            }
+        }
+
+        Ok(cache
+            .iter()
+            .map(|(uri, pulled)| (uri.clone(), pulled.items.clone()))
+            .collect())
+    }
+
+    /// Returns the current diagnostics for a document, whether the server pushes or is pulled.
+    ///
+    /// Combines the diagnostics the server published with those pulled via
+    /// `textDocument/diagnostic` when the server supports pull diagnostics.
+    /// ## Errors
+    pub async fn diagnostics(&self, path: &Path) -> LspResult<Vec<Diagnostic>> {
+        let uri = path_to_url(path)?;
+        let mut diagnostics = self
+            .published_diagnostics()
+            .remove(&uri)
+            .unwrap_or_default();
+
+        if self.diagnostic_options().is_some() {
+            let pulled = self.pull_document_diagnostics(uri).await?;
+            merge_diagnostics(&mut diagnostics, pulled);
+        }
+
+        Ok(diagnostics)
+    }
+
+    /// Returns the current diagnostics for all known documents, whether the server pushes or is pulled.
+    ///
+    /// Combines published diagnostics with pulled ones: via `workspace/diagnostic` when
+    /// the server supports it, otherwise via `textDocument/diagnostic` for each open document.
+    /// ## Errors
+    pub async fn all_diagnostics(&self) -> LspResult<HashMap<Url, Vec<Diagnostic>>> {
+        let mut all = self.published_diagnostics();
+
+        let pulled = match self.diagnostic_options() {
+            Some(options) if options.workspace_diagnostics => self.workspace_diagnostics().await?,
+            Some(_) => {
+                let open_documents: Vec<Url> =
+                    self.open_documents.lock().await.iter().cloned().collect();
+                let mut pulled = HashMap::new();
+                for uri in open_documents {
+                    let diagnostics = self.pull_document_diagnostics(uri.clone()).await?;
+                    pulled.insert(uri, diagnostics);
+                }
+                pulled
+            }
+            None => HashMap::new(),
+        };
+        for (uri, diagnostics) in pulled {
+            merge_diagnostics(all.entry(uri).or_default(), diagnostics);
+        }
+        all.retain(|_, diagnostics| !diagnostics.is_empty());
+
+        Ok(all)
+    }
+
+    /// Lists the runnables (binaries, tests, benches, doc-tests) defined in a document.
+    ///
+    /// Uses rust-analyzer's `experimental/runnables` extension.
+    /// ## Errors
+    pub async fn runnables(&self, path: &Path) -> LspResult<Vec<Runnable>> {
+        let uri = path_to_url(path)?;
+
+        let params = RunnablesParams {
+            text_document: TextDocumentIdentifier { uri },
+            position: None,
+        };
+
+        tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.request::<Runnables>(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(Runnables::METHOD, e))
+    }
+
+    /// Finds the tests that exercise the item at the given position.
+    ///
+    /// Uses rust-analyzer's `rust-analyzer/relatedTests` extension.
+    /// ## Errors
+    pub async fn related_tests(
+        &self,
+        path: &Path,
+        line: u32,
+        column: u32,
+    ) -> LspResult<Vec<TestInfo>> {
+        let uri = path_to_url(path)?;
+        let position = to_lsp_position(line, column)?;
+
+        let params = TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri },
+            position,
+        };
+
+        tokio::time::timeout(
+            self.config.request_timeout,
+            self.server.lock().await.request::<RelatedTests>(params),
+        )
+        .await
+        .map_err(|_| LspError::Timeout(self.config.request_timeout))?
+        .map_err(|e| request_error(RelatedTests::METHOD, e))
+    }
 }
 
 #[cfg(test)]
@@ -837,7 +1909,7 @@ mod tests {
             .server_command("pylsp")
             .server_args(["--verbose"])
             .workspace_root("/home/user/project")
-            .init_timeout(std::time::Duration::from_secs(60));
+            .init_timeout(std::time::Duration::from_mins(1));
 
         assert_eq!(builder.config.server_command, "pylsp");
         assert_eq!(builder.config.server_args, vec!["--verbose"]);
@@ -847,7 +1919,7 @@ mod tests {
         );
         assert_eq!(
             builder.config.init_timeout,
-            std::time::Duration::from_secs(60)
+            std::time::Duration::from_mins(1)
         );
     }
 }
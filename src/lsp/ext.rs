@@ -0,0 +1,513 @@
+//! rust-analyzer LSP extensions.
+//!
+//! This module defines typed requests for the non-standard methods that
+//! rust-analyzer exposes on top of the Language Server Protocol. The shapes
+//! follow rust-analyzer's `lsp-extensions.md` and are sent through
+//! `ServerSocket::request` like any other request.
+//!
+//! Note: Types here are used by the LSP client and may appear unused in the
+//! binary target.
+
+// Allow dead code warnings for types used by the library API
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use lsp_types::notification::Notification;
+use lsp_types::request::Request;
+use lsp_types::{
+    GotoDefinitionResponse, LocationLink, Position, Range, TextDocumentIdentifier,
+    TextDocumentPositionParams, Url, WorkspaceEdit,
+};
+use serde::{Deserialize, Serialize};
+
+/// Recursively expands the macro call at a position (`rust-analyzer/expandMacro`).
+#[derive(Debug)]
+pub enum ExpandMacro {}
+
+impl Request for ExpandMacro {
+    type Params = ExpandMacroParams;
+    type Result = Option<ExpandedMacro>;
+    const METHOD: &'static str = "rust-analyzer/expandMacro";
+}
+
+/// Parameters for the `rust-analyzer/expandMacro` request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpandMacroParams {
+    /// The document containing the macro call.
+    pub text_document: TextDocumentIdentifier,
+    /// Position inside the macro call or attribute.
+    pub position: Position,
+}
+
+/// Result of a macro expansion.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpandedMacro {
+    /// Name of the expanded macro, e.g. `println!` or `Debug`.
+    pub name: String,
+    /// The recursively expanded source code.
+    pub expansion: String,
+}
+
+/// Finds the declaration of the module containing a position (`experimental/parentModule`).
+///
+/// Returns the `mod` declaration in the parent module, or a link to `Cargo.toml` for a
+/// crate root. On a `mod` declaration, the parent of the declared module is returned.
+#[derive(Debug)]
+pub enum ParentModule {}
+
+impl Request for ParentModule {
+    type Params = TextDocumentPositionParams;
+    type Result = Option<GotoDefinitionResponse>;
+    const METHOD: &'static str = "experimental/parentModule";
+}
+
+/// Finds the `mod` declarations of the submodules of a module (`experimental/childModules`).
+///
+/// On a `mod` declaration, the submodules of the declared module are returned.
+#[derive(Debug)]
+pub enum ChildModules {}
+
+impl Request for ChildModules {
+    type Params = TextDocumentPositionParams;
+    type Result = Option<GotoDefinitionResponse>;
+    const METHOD: &'static str = "experimental/childModules";
+}
+
+/// Runs a structural search and replace rule (`experimental/ssr`).
+///
+/// Returns the edit that would apply the rule; nothing is changed on disk.
+#[derive(Debug)]
+pub enum Ssr {}
+
+impl Request for Ssr {
+    type Params = SsrParams;
+    type Result = WorkspaceEdit;
+    const METHOD: &'static str = "experimental/ssr";
+}
+
+/// Parameters for the `experimental/ssr` request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SsrParams {
+    /// The rule, e.g. `foo($a, $b) ==>> bar($b, $a)`.
+    pub query: String,
+    /// Only check that the rule parses, without searching for matches.
+    pub parse_only: bool,
+    /// Document and position whose scope is used to resolve paths in the rule.
+    #[serde(flatten)]
+    pub position: TextDocumentPositionParams,
+    /// Ranges in the document to restrict matches to; empty searches the whole workspace.
+    pub selections: Vec<Range>,
+}
+
+/// Reloads the workspace after changes to `Cargo.toml` files (`rust-analyzer/reloadWorkspace`).
+#[derive(Debug)]
+pub enum ReloadWorkspace {}
+
+impl Request for ReloadWorkspace {
+    type Params = ();
+    type Result = ();
+    const METHOD: &'static str = "rust-analyzer/reloadWorkspace";
+}
+
+/// Re-runs build scripts and rebuilds proc macros (`rust-analyzer/rebuildProcMacros`).
+#[derive(Debug)]
+pub enum RebuildProcMacros {}
+
+impl Request for RebuildProcMacros {
+    type Params = ();
+    type Result = ();
+    const METHOD: &'static str = "rust-analyzer/rebuildProcMacros";
+}
+
+/// Returns a plain-text report of the server's internal state (`rust-analyzer/analyzerStatus`).
+#[derive(Debug)]
+pub enum AnalyzerStatus {}
+
+impl Request for AnalyzerStatus {
+    type Params = AnalyzerStatusParams;
+    type Result = String;
+    const METHOD: &'static str = "rust-analyzer/analyzerStatus";
+}
+
+/// Parameters for the `rust-analyzer/analyzerStatus` request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyzerStatusParams {
+    /// Document whose crates should be included in the report.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_document: Option<TextDocumentIdentifier>,
+}
+
+/// Returns a plain-text report of the server's memory usage (`rust-analyzer/memoryUsage`).
+///
+/// Only available in rust-analyzer builds with memory profiling enabled.
+#[derive(Debug)]
+pub enum MemoryUsage {}
+
+impl Request for MemoryUsage {
+    type Params = ();
+    type Result = String;
+    const METHOD: &'static str = "rust-analyzer/memoryUsage";
+}
+
+/// Renders the crate graph in Graphviz DOT format (`rust-analyzer/viewCrateGraph`).
+#[derive(Debug)]
+pub enum ViewCrateGraph {}
+
+impl Request for ViewCrateGraph {
+    type Params = ViewCrateGraphParams;
+    type Result = String;
+    const METHOD: &'static str = "rust-analyzer/viewCrateGraph";
+}
+
+/// Parameters for the `rust-analyzer/viewCrateGraph` request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewCrateGraphParams {
+    /// Include all crates, not just the workspace members.
+    pub full: bool,
+}
+
+/// Lists the dependencies of the workspace (`rust-analyzer/fetchDependencyList`).
+#[derive(Debug)]
+pub enum FetchDependencyList {}
+
+impl Request for FetchDependencyList {
+    type Params = FetchDependencyListParams;
+    type Result = FetchDependencyListResult;
+    const METHOD: &'static str = "rust-analyzer/fetchDependencyList";
+}
+
+/// Parameters for the `rust-analyzer/fetchDependencyList` request.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FetchDependencyListParams {}
+
+/// Result of the `rust-analyzer/fetchDependencyList` request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FetchDependencyListResult {
+    /// The dependencies, including sysroot crates such as `std`.
+    pub crates: Vec<CrateInfo>,
+}
+
+/// A dependency of the workspace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrateInfo {
+    /// Crate name, if known.
+    pub name: Option<String>,
+    /// Crate version, if known.
+    pub version: Option<String>,
+    /// Source root of the crate on disk.
+    pub path: Url,
+}
+
+/// Returns the syntax tree of a document (`rust-analyzer/viewSyntaxTree`).
+///
+/// Recent rust-analyzer versions return the tree as JSON, see [`SyntaxTreeNode`]; older
+/// versions return an indented text dump.
+#[derive(Debug)]
+pub enum ViewSyntaxTree {}
+
+impl Request for ViewSyntaxTree {
+    type Params = ViewSyntaxTreeParams;
+    type Result = String;
+    const METHOD: &'static str = "rust-analyzer/viewSyntaxTree";
+}
+
+/// Parameters for the `rust-analyzer/viewSyntaxTree` request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewSyntaxTreeParams {
+    /// The document to show the syntax tree of.
+    pub text_document: TextDocumentIdentifier,
+}
+
+/// A node or token in the JSON syntax tree returned by `rust-analyzer/viewSyntaxTree`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyntaxTreeNode {
+    /// Whether this is a `Node` or a `Token`.
+    #[serde(rename = "type")]
+    pub node_type: String,
+    /// Syntax kind, e.g. `FN` or `IDENT`.
+    pub kind: String,
+    /// Start as `[byte offset, line, column]`, with 0-indexed line and column.
+    pub start: [u32; 3],
+    /// End as `[byte offset, line, column]`, with 0-indexed line and column.
+    pub end: [u32; 3],
+    /// Child nodes and tokens; empty for tokens.
+    #[serde(default)]
+    pub children: Vec<SyntaxTreeNode>,
+}
+
+/// Returns the HIR of the function at a position (`rust-analyzer/viewHir`).
+#[derive(Debug)]
+pub enum ViewHir {}
+
+impl Request for ViewHir {
+    type Params = TextDocumentPositionParams;
+    type Result = String;
+    const METHOD: &'static str = "rust-analyzer/viewHir";
+}
+
+/// Returns the MIR of the function at a position (`rust-analyzer/viewMir`).
+#[derive(Debug)]
+pub enum ViewMir {}
+
+impl Request for ViewMir {
+    type Params = TextDocumentPositionParams;
+    type Result = String;
+    const METHOD: &'static str = "rust-analyzer/viewMir";
+}
+
+/// Resolves the documentation URLs of the item at a position (`experimental/externalDocs`).
+#[derive(Debug)]
+pub enum ExternalDocs {}
+
+impl Request for ExternalDocs {
+    type Params = TextDocumentPositionParams;
+    type Result = ExternalDocsResponse;
+    const METHOD: &'static str = "experimental/externalDocs";
+}
+
+/// Result of `experimental/externalDocs`.
+///
+/// Servers answer with the web URL only unless the client advertises the
+/// `localDocs` experimental capability.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ExternalDocsResponse {
+    /// Only the web URL, e.g. on docs.rs.
+    Simple(Option<Url>),
+    /// Web URL and local `cargo doc` URL.
+    WithLocal(ExternalDocsPair),
+}
+
+/// Web and local documentation URLs of an item.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExternalDocsPair {
+    /// URL on docs.rs or doc.rust-lang.org.
+    pub web: Option<Url>,
+    /// `file://` URL into `target/doc` or the toolchain docs; the file may not exist yet.
+    pub local: Option<Url>,
+}
+
+impl From<ExternalDocsResponse> for ExternalDocsPair {
+    fn from(response: ExternalDocsResponse) -> Self {
+        match response {
+            ExternalDocsResponse::Simple(web) => Self { web, local: None },
+            ExternalDocsResponse::WithLocal(pair) => pair,
+        }
+    }
+}
+
+/// Starts flycheck, i.e. `cargo check` or clippy (`rust-analyzer/runFlycheck`).
+///
+/// Progress is reported with `$/progress` under tokens starting with
+/// [`FLYCHECK_PROGRESS_TOKEN`], and results are published as diagnostics.
+#[derive(Debug)]
+pub enum RunFlycheck {}
+
+impl Notification for RunFlycheck {
+    type Params = RunFlycheckParams;
+    const METHOD: &'static str = "rust-analyzer/runFlycheck";
+}
+
+/// Parameters for the `rust-analyzer/runFlycheck` notification.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunFlycheckParams {
+    /// Document whose workspace should be checked; `None` checks all workspaces.
+    pub text_document: Option<TextDocumentIdentifier>,
+}
+
+/// Prefix of the `$/progress` tokens rust-analyzer uses for flycheck.
+pub const FLYCHECK_PROGRESS_TOKEN: &str = "rust-analyzer/flycheck/";
+
+/// Lists runnables (binaries, tests, benches, doc-tests) in a document (`experimental/runnables`).
+#[derive(Debug)]
+pub enum Runnables {}
+
+impl Request for Runnables {
+    type Params = RunnablesParams;
+    type Result = Vec<Runnable>;
+    const METHOD: &'static str = "experimental/runnables";
+}
+
+/// Parameters for the `experimental/runnables` request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunnablesParams {
+    /// The document to list runnables for.
+    pub text_document: TextDocumentIdentifier,
+    /// Optional position to restrict results to the runnables around it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<Position>,
+}
+
+/// Finds the tests that exercise the item at a position (`rust-analyzer/relatedTests`).
+#[derive(Debug)]
+pub enum RelatedTests {}
+
+impl Request for RelatedTests {
+    type Params = TextDocumentPositionParams;
+    type Result = Vec<TestInfo>;
+    const METHOD: &'static str = "rust-analyzer/relatedTests";
+}
+
+/// A test related to an item.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestInfo {
+    /// The runnable that executes the test.
+    pub runnable: Runnable,
+}
+
+/// Something rust-analyzer knows how to run: a binary, test, bench or doc-test.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Runnable {
+    /// Human-readable label, e.g. `test calculator::tests::test_adder`.
+    pub label: String,
+    /// Where the runnable is defined.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<LocationLink>,
+    /// How the runnable is executed.
+    pub kind: RunnableKind,
+    /// Arguments for executing the runnable.
+    pub args: RunnableArgs,
+}
+
+/// How a runnable is executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunnableKind {
+    /// Run through cargo.
+    Cargo,
+    /// Run as a shell command.
+    Shell,
+}
+
+/// Arguments of a runnable, depending on its kind.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RunnableArgs {
+    /// Arguments for a cargo invocation.
+    Cargo(CargoRunnableArgs),
+    /// Arguments for a shell command.
+    Shell(ShellRunnableArgs),
+}
+
+/// Arguments for running a runnable through cargo.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CargoRunnableArgs {
+    /// Root of the cargo workspace the runnable belongs to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_root: Option<PathBuf>,
+    /// Directory to run cargo in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+    /// Command to use instead of `cargo`, if configured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub override_cargo: Option<String>,
+    /// Arguments passed to cargo, e.g. `["test", "--package", "foo", "--lib"]`.
+    pub cargo_args: Vec<String>,
+    /// Arguments passed to the executable after `--`.
+    #[serde(default)]
+    pub executable_args: Vec<String>,
+    /// Extra environment variables for the command.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<HashMap<String, String>>,
+}
+
+/// Arguments for running a runnable as a shell command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellRunnableArgs {
+    /// Program to execute.
+    pub program: String,
+    /// Arguments passed to the program.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Directory to run the program in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+}
+
+impl Runnable {
+    /// Returns a ready-to-run command line for this runnable.
+    ///
+    /// Cargo runnables render as `cargo <cargo args> -- <executable args>`. Arguments
+    /// containing whitespace or quotes are single-quoted.
+    pub fn command_line(&self) -> String {
+        let words: Vec<&str> = match &self.args {
+            RunnableArgs::Cargo(args) => {
+                let mut words = vec![args.override_cargo.as_deref().unwrap_or("cargo")];
+                words.extend(args.cargo_args.iter().map(String::as_str));
+                if !args.executable_args.is_empty() {
+                    words.push("--");
+                    words.extend(args.executable_args.iter().map(String::as_str));
+                }
+                words
+            }
+            RunnableArgs::Shell(args) => std::iter::once(args.program.as_str())
+                .chain(args.args.iter().map(String::as_str))
+                .collect(),
+        };
+
+        words
+            .into_iter()
+            .map(|word| {
+                if word.is_empty()
+                    || word.contains(|c: char| c.is_whitespace() || c == '\'' || c == '"')
+                {
+                    format!("'{}'", word.replace('\'', r"'\''"))
+                } else {
+                    word.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runnable_command_line() {
+        let json = r#"{
+            "label": "test calculator::tests::test_adder",
+            "kind": "cargo",
+            "args": {
+                "cwd": "/tmp/project",
+                "overrideCargo": null,
+                "workspaceRoot": "/tmp/project",
+                "cargoArgs": ["test", "--package", "sample_project", "--lib"],
+                "executableArgs": ["calculator::tests::test_adder", "--exact", "--nocapture"]
+            }
+        }"#;
+        let runnable: Runnable = serde_json::from_str(json).unwrap();
+
+        assert!(matches!(runnable.args, RunnableArgs::Cargo(_)));
+        assert_eq!(
+            runnable.command_line(),
+            "cargo test --package sample_project --lib -- calculator::tests::test_adder --exact --nocapture"
+        );
+    }
+
+    #[test]
+    fn test_shell_runnable_command_line() {
+        let json = r#"{
+            "label": "run demo",
+            "kind": "shell",
+            "args": { "program": "demo", "args": ["--name", "two words"] }
+        }"#;
+        let runnable: Runnable = serde_json::from_str(json).unwrap();
+
+        assert_eq!(runnable.kind, RunnableKind::Shell);
+        assert_eq!(runnable.command_line(), "demo --name 'two words'");
+    }
+}
@@ -7,6 +7,7 @@
 //!
 //! The LSP module is organized into:
 //! - `client`: The main LSP client implementation
+//! - `ext`: Typed requests for rust-analyzer LSP extensions
 //! - `types`: Additional type definitions for LSP operations
 //!
 //! # Usage
@@ -19,6 +20,7 @@
 //! ```
 
 pub mod client;
+pub mod ext;
 pub mod types;
 
 // TODO: Phase 3 - Implement LSP client
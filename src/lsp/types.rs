@@ -101,6 +101,28 @@ pub fn from_lsp_position(position: Position) -> (u32, u32) {
     (position.line + 1, position.character + 1)
 }
 
+/// Converts an LSP character offset (UTF-16 code units) into a byte index within `line`.
+///
+/// Offsets past the end of the line are clamped to the line length.
+pub fn utf16_offset_to_byte(line: &str, offset: u32) -> usize {
+    let mut units = 0;
+    for (idx, ch) in line.char_indices() {
+        if units >= offset as usize {
+            return idx;
+        }
+        units += ch.len_utf16();
+    }
+    line.len()
+}
+
+/// Converts a byte index within `line` into an LSP character offset (UTF-16 code units).
+///
+/// Indices past the end of the line, or inside a character, are clamped to the line length.
+pub fn byte_to_utf16_offset(line: &str, byte: usize) -> u32 {
+    let prefix = line.get(..byte).unwrap_or(line);
+    u32::try_from(prefix.encode_utf16().count()).unwrap_or(u32::MAX)
+}
+
 /// Converts an LSP symbol kind to a human-readable string.
 pub fn symbol_kind_to_string(kind: lsp_types::SymbolKind) -> &'static str {
     use lsp_types::SymbolKind;
@@ -135,6 +157,65 @@ pub fn symbol_kind_to_string(kind: lsp_types::SymbolKind) -> &'static str {
     }
 }
 
+/// Converts an LSP completion item kind to a human-readable string.
+pub fn completion_kind_to_string(kind: lsp_types::CompletionItemKind) -> &'static str {
+    use lsp_types::CompletionItemKind;
+    match kind {
+        CompletionItemKind::TEXT => "text",
+        CompletionItemKind::METHOD => "method",
+        CompletionItemKind::FUNCTION => "function",
+        CompletionItemKind::CONSTRUCTOR => "constructor",
+        CompletionItemKind::FIELD => "field",
+        CompletionItemKind::VARIABLE => "variable",
+        CompletionItemKind::CLASS => "class",
+        CompletionItemKind::INTERFACE => "interface",
+        CompletionItemKind::MODULE => "module",
+        CompletionItemKind::PROPERTY => "property",
+        CompletionItemKind::UNIT => "unit",
+        CompletionItemKind::VALUE => "value",
+        CompletionItemKind::ENUM => "enum",
+        CompletionItemKind::KEYWORD => "keyword",
+        CompletionItemKind::SNIPPET => "snippet",
+        CompletionItemKind::COLOR => "color",
+        CompletionItemKind::FILE => "file",
+        CompletionItemKind::REFERENCE => "reference",
+        CompletionItemKind::FOLDER => "folder",
+        CompletionItemKind::ENUM_MEMBER => "enum_member",
+        CompletionItemKind::CONSTANT => "constant",
+        CompletionItemKind::STRUCT => "struct",
+        CompletionItemKind::EVENT => "event",
+        CompletionItemKind::OPERATOR => "operator",
+        CompletionItemKind::TYPE_PARAMETER => "type_parameter",
+        _ => "unknown",
+    }
+}
+
+/// Converts an LSP document highlight kind to a human-readable string.
+///
+/// A missing kind is treated as a textual occurrence, as the LSP specification requires.
+pub fn highlight_kind_to_string(kind: Option<lsp_types::DocumentHighlightKind>) -> &'static str {
+    use lsp_types::DocumentHighlightKind;
+    match kind {
+        Some(DocumentHighlightKind::READ) => "read",
+        Some(DocumentHighlightKind::WRITE) => "write",
+        _ => "text",
+    }
+}
+
+/// Converts an LSP diagnostic severity to a human-readable string.
+pub fn diagnostic_severity_to_string(
+    severity: Option<lsp_types::DiagnosticSeverity>,
+) -> &'static str {
+    use lsp_types::DiagnosticSeverity;
+    match severity {
+        Some(DiagnosticSeverity::ERROR) => "error",
+        Some(DiagnosticSeverity::WARNING) => "warning",
+        Some(DiagnosticSeverity::INFORMATION) => "info",
+        Some(DiagnosticSeverity::HINT) => "hint",
+        _ => "unknown",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -189,6 +270,71 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_utf16_offset_to_byte() {
+        assert_eq!(utf16_offset_to_byte("let x = 1;", 5), 5);
+        assert_eq!(utf16_offset_to_byte("let x = 1;", 100), 10);
+        // 'é' is one UTF-16 unit but two bytes, '😀' is two UTF-16 units and four bytes
+        assert_eq!(utf16_offset_to_byte("é = 1", 1), 2);
+        assert_eq!(utf16_offset_to_byte("😀x", 2), 4);
+    }
+
+    #[test]
+    fn test_byte_to_utf16_offset() {
+        assert_eq!(byte_to_utf16_offset("let x = 1;", 4), 4);
+        assert_eq!(byte_to_utf16_offset("let x = 1;", 100), 10);
+        assert_eq!(byte_to_utf16_offset("é = 1", 2), 1);
+        assert_eq!(byte_to_utf16_offset("😀x", 4), 2);
+    }
+
+    #[test]
+    fn test_completion_kind_to_string() {
+        assert_eq!(
+            completion_kind_to_string(lsp_types::CompletionItemKind::METHOD),
+            "method"
+        );
+        assert_eq!(
+            completion_kind_to_string(lsp_types::CompletionItemKind::FIELD),
+            "field"
+        );
+    }
+
+    #[test]
+    fn test_highlight_kind_to_string() {
+        use lsp_types::DocumentHighlightKind;
+        assert_eq!(
+            highlight_kind_to_string(Some(DocumentHighlightKind::READ)),
+            "read"
+        );
+        assert_eq!(
+            highlight_kind_to_string(Some(DocumentHighlightKind::WRITE)),
+            "write"
+        );
+        assert_eq!(
+            highlight_kind_to_string(Some(DocumentHighlightKind::TEXT)),
+            "text"
+        );
+        assert_eq!(highlight_kind_to_string(None), "text");
+    }
+
+    #[test]
+    fn test_diagnostic_severity_to_string() {
+        use lsp_types::DiagnosticSeverity;
+        assert_eq!(
+            diagnostic_severity_to_string(Some(DiagnosticSeverity::ERROR)),
+            "error"
+        );
+        assert_eq!(
+            diagnostic_severity_to_string(Some(DiagnosticSeverity::WARNING)),
+            "warning"
+        );
+        assert_eq!(
+            diagnostic_severity_to_string(Some(DiagnosticSeverity::HINT)),
+            "hint"
+        );
+        assert_eq!(diagnostic_severity_to_string(None), "unknown");
+    }
+
     #[test]
     fn test_path_to_uri() {
         // Create a temporary file for testing
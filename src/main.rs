@@ -18,6 +18,7 @@ mod mcp;
 
 use lsp::client::LspClient;
 use mcp::KadabraRunes;
+use mcp::paths::PathLabels;
 
 /// MCP server for semantic code navigation via language servers.
 #[derive(Parser, Debug)]
@@ -58,6 +59,15 @@ struct ServeArgs {
     /// Log level: trace, debug, info, warn, error.
     #[arg(long, default_value = "info")]
     log_level: String,
+
+    /// Further directory whose files tools may read, e.g. a path dependency outside
+    /// the workspace. May be repeated.
+    #[arg(long)]
+    allow_path: Vec<PathBuf>,
+
+    /// Let tools read files anywhere, not only in the workspace and dependency roots.
+    #[arg(long)]
+    allow_any_path: bool,
 }
 
 /// Arguments for the config command
@@ -126,6 +136,8 @@ async fn main() -> Result<()> {
                 language_server: "rust-analyzer".to_string(),
                 language_server_args: vec![],
                 log_level: "info".to_string(),
+                allow_path: vec![],
+                allow_any_path: false,
             })
             .await
         }
@@ -169,7 +181,10 @@ async fn run_serve(args: ServeArgs) -> Result<()> {
     info!("LSP client initialized successfully");
 
     // Create KadabraRunes instance with LSP client
-    let server = KadabraRunes::new(workspace, Arc::new(lsp_client));
+    let paths = PathLabels::new(workspace.clone())
+        .with_allowed_roots(args.allow_path)
+        .allow_any(args.allow_any_path);
+    let server = KadabraRunes::new(workspace, Arc::new(lsp_client)).with_path_labels(paths);
 
     info!("starting MCP server with stdio transport");
 
@@ -203,6 +218,8 @@ mod tests {
             language_server: "rust-analyzer".to_string(),
             language_server_args: vec![],
             log_level: "debug".to_string(),
+            allow_path: vec![],
+            allow_any_path: false,
         };
         assert_eq!(args.parse_log_level().unwrap(), Level::DEBUG);
     }
@@ -9,6 +9,8 @@
 //! The MCP module is organized into:
 //! - `transport`: Handles stdio-based JSON-RPC communication
 //! - `tools`: Defines and implements the navigation tools
+//! - `symbol_path`: Parses and matches Rust paths addressing symbols
+//! - `paths`: Renders file paths in short forms and resolves tool inputs within the allowed roots
 //!
 //! # Usage
 //!
@@ -19,7 +21,9 @@
 //! server.run().await?;
 //! ```
 
+pub mod paths;
 pub mod server;
+pub mod symbol_path;
 pub mod tools;
 // pub mod transport;
 
@@ -0,0 +1,602 @@
+//! Short path forms for tool output and input.
+//!
+//! Absolute paths waste tokens and reveal the developer's home directory, so tool
+//! output renders paths in short forms, and tools taking a path accept them back:
+//! - Workspace files relative to the workspace root: `src/main.rs`
+//! - Registry dependencies by crate, version and path in the crate:
+//!   `tokio@1.48.0:src/net/tcp/stream.rs`
+//! - Standard library sources by path below the sysroot's library directory:
+//!   `std:core/src/option.rs`
+//! - Other files in the home directory with `~`: `~/.cargo/git/checkouts/...`
+//!
+//! Anything else keeps its absolute path.
+//!
+//! Paths given to tools may also be absolute, relative to the workspace root, or
+//! `file://` URIs. They must lie in the workspace, Cargo's registry or git checkout
+//! sources, the standard library sources or an additionally allowed root, so that
+//! clients cannot read arbitrary files, such as Cargo's credentials, through the
+//! server.
+
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+use lsp_types::Url;
+use thiserror::Error;
+
+/// Prefix of standard library paths.
+const STD_PREFIX: &str = "std:";
+
+/// Components from the sysroot to its library sources.
+const SYSROOT_LIBRARY: [&str; 4] = ["rustlib", "src", "rust", "library"];
+
+/// Directories below Cargo's home holding dependency sources.
+const CARGO_SOURCES: [&str; 2] = ["registry/src", "git/checkouts"];
+
+/// Maximum number of paths suggested for a missing file.
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Maximum number of directory entries visited while looking for suggestions.
+const MAX_SUGGESTION_SCAN: usize = 20_000;
+
+/// Errors resolving a path given to a tool.
+#[derive(Debug, Error)]
+pub enum PathError {
+    /// The path is a URI that does not name a local file.
+    #[error("invalid file URI: {0}")]
+    InvalidUri(String),
+
+    /// The path lies outside the workspace and dependency roots.
+    #[error(
+        "path is outside the workspace and dependency roots: {0} (start the server with --allow-path to permit it)"
+    )]
+    OutsideRoots(String),
+
+    /// No file exists at the path.
+    #[error("file not found: {path}{}", format_suggestions(suggestions))]
+    NotFound {
+        /// The path as given.
+        path: String,
+        /// Existing paths the caller may have meant, in their short forms.
+        suggestions: Vec<String>,
+    },
+}
+
+/// Renders paths in their short forms and resolves short forms back to paths.
+#[derive(Debug)]
+pub struct PathLabels {
+    /// Root directory of the workspace.
+    workspace_root: PathBuf,
+    /// The user's home directory.
+    home: Option<PathBuf>,
+    /// Cargo's home directory, holding the registry sources.
+    cargo_home: Option<PathBuf>,
+    /// Library directory of the sysroot, learned from the first standard library path
+    /// rendered or from `rustc --print sysroot`.
+    sysroot_library: OnceLock<Option<PathBuf>>,
+    /// Further directories whose files tools may read.
+    allowed_roots: Vec<PathBuf>,
+    /// Whether tools may read files anywhere.
+    allow_any: bool,
+}
+
+impl PathLabels {
+    /// Creates labels for a workspace, locating the home and Cargo directories from
+    /// the environment.
+    pub fn new(workspace_root: PathBuf) -> Self {
+        let home = std::env::var_os("HOME")
+            .or_else(|| std::env::var_os("USERPROFILE"))
+            .map(PathBuf::from);
+        let cargo_home = std::env::var_os("CARGO_HOME")
+            .map(PathBuf::from)
+            .or_else(|| home.as_ref().map(|home| home.join(".cargo")));
+        Self::with_dirs(workspace_root, home, cargo_home)
+    }
+
+    /// Creates labels with explicit home and Cargo directories.
+    pub fn with_dirs(
+        workspace_root: PathBuf,
+        home: Option<PathBuf>,
+        cargo_home: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            workspace_root,
+            home,
+            cargo_home,
+            sysroot_library: OnceLock::new(),
+            allowed_roots: Vec::new(),
+            allow_any: false,
+        }
+    }
+
+    /// Adds directories, such as path dependencies outside the workspace, whose files
+    /// tools may read.
+    #[must_use]
+    pub fn with_allowed_roots(mut self, roots: impl IntoIterator<Item = PathBuf>) -> Self {
+        self.allowed_roots.extend(roots);
+        self
+    }
+
+    /// Lets tools read files anywhere instead of only below the allowed roots.
+    #[must_use]
+    pub fn allow_any(mut self, allow_any: bool) -> Self {
+        self.allow_any = allow_any;
+        self
+    }
+
+    /// Returns the short form of `path`.
+    pub fn label(&self, path: &Path) -> String {
+        if let Ok(relative) = path.strip_prefix(&self.workspace_root)
+            && !relative.as_os_str().is_empty()
+        {
+            return join_components(relative);
+        }
+
+        let components: Vec<_> = path
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name.to_str().unwrap_or_default()),
+                _ => None,
+            })
+            .collect();
+
+        // <cargo home>/registry/src/<index>/<name>-<version>/<path in crate>
+        if let Some(index) = components
+            .windows(2)
+            .position(|window| window == ["registry", "src"])
+            && let Some(crate_dir) = components.get(index + 3)
+            && let Some((name, version)) = split_crate_dir(crate_dir)
+            && components.len() > index + 4
+        {
+            return format!("{name}@{version}:{}", components[index + 4..].join("/"));
+        }
+
+        // <sysroot>/lib/rustlib/src/rust/library/<crate>/...
+        if let Some(index) = components
+            .windows(SYSROOT_LIBRARY.len())
+            .position(|window| window == SYSROOT_LIBRARY)
+        {
+            let library_end = index + SYSROOT_LIBRARY.len();
+            if components.len() > library_end {
+                let mut normal = 0;
+                let library: PathBuf = path
+                    .components()
+                    .take_while(|component| {
+                        if normal == library_end {
+                            return false;
+                        }
+                        if matches!(component, Component::Normal(_)) {
+                            normal += 1;
+                        }
+                        true
+                    })
+                    .collect();
+                let _ = self.sysroot_library.set(Some(library));
+                return format!("{STD_PREFIX}{}", components[library_end..].join("/"));
+            }
+        }
+
+        if let Some(home) = &self.home
+            && let Ok(relative) = path.strip_prefix(home)
+        {
+            return format!("~/{}", join_components(relative));
+        }
+
+        path.display().to_string()
+    }
+
+    /// Returns the short form of a file URI, or the URI itself for other schemes.
+    pub fn label_uri(&self, uri: &Url) -> String {
+        uri.to_file_path()
+            .map_or_else(|()| uri.to_string(), |path| self.label(&path))
+    }
+
+    /// Resolves a dependency, standard library or home-relative short form to a path.
+    ///
+    /// Returns `None` for text in none of these forms, such as workspace-relative or
+    /// absolute paths, and for dependencies not found in the registry.
+    pub fn resolve(&self, text: &str) -> Option<PathBuf> {
+        if let Some(rest) = text.strip_prefix(STD_PREFIX) {
+            return Some(self.sysroot_library()?.join(rest));
+        }
+        if let Some(rest) = text.strip_prefix("~/") {
+            return Some(self.home.as_ref()?.join(rest));
+        }
+
+        let (krate, rest) = text.split_once(':')?;
+        let (name, version) = krate.split_once('@')?;
+        if name.is_empty() || rest.starts_with('/') || !is_version(version) {
+            return None;
+        }
+        let registry = self.cargo_home.as_ref()?.join("registry").join("src");
+        let crate_dir = format!("{name}-{version}");
+        std::fs::read_dir(registry)
+            .ok()?
+            .filter_map(Result::ok)
+            .map(|index| index.path().join(&crate_dir))
+            .find(|dir| dir.is_dir())
+            .map(|dir| dir.join(rest))
+    }
+
+    /// Resolves a file path given to a tool.
+    ///
+    /// Accepts `file://` URIs, the short forms rendered by [`Self::label`], absolute
+    /// paths and paths relative to the workspace root. The file must exist and, unless
+    /// [`Self::allow_any`] is set, lie below the workspace, Cargo's registry or git
+    /// checkout sources, the standard library sources or a root from
+    /// [`Self::with_allowed_roots`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PathError`] for URIs that are not local files, for paths outside the
+    /// allowed roots, and for missing files, suggesting existing paths.
+    pub fn resolve_input(&self, text: &str) -> Result<PathBuf, PathError> {
+        let text = text.trim();
+        let path = if text.starts_with("file:") {
+            Url::parse(text)
+                .ok()
+                .and_then(|uri| uri.to_file_path().ok())
+                .ok_or_else(|| PathError::InvalidUri(text.to_string()))?
+        } else if let Some(path) = self.resolve(text) {
+            path
+        } else {
+            self.workspace_root.join(text)
+        };
+
+        // Check the path as written first, so that the existence of files outside the
+        // roots is not revealed, then the real path, so that symlinks cannot escape.
+        let path = normalize(&path);
+        if !self.is_allowed(&path) {
+            return Err(PathError::OutsideRoots(text.to_string()));
+        }
+        let Ok(real_path) = path.canonicalize() else {
+            return Err(PathError::NotFound {
+                path: text.to_string(),
+                suggestions: self.suggestions(&path),
+            });
+        };
+        if !self.is_allowed(&real_path) {
+            return Err(PathError::OutsideRoots(text.to_string()));
+        }
+        if !real_path.is_file() {
+            return Err(PathError::NotFound {
+                path: text.to_string(),
+                suggestions: Vec::new(),
+            });
+        }
+        Ok(real_path)
+    }
+
+    /// Returns whether tools may read `path`, a normalized absolute path.
+    fn is_allowed(&self, path: &Path) -> bool {
+        if self.allow_any {
+            return true;
+        }
+        let cargo_sources = self
+            .cargo_home
+            .iter()
+            .flat_map(|cargo_home| CARGO_SOURCES.map(|dir| cargo_home.join(dir)));
+        let mut roots: Vec<PathBuf> = [self.workspace_root.clone()]
+            .into_iter()
+            .chain(cargo_sources)
+            .chain(self.allowed_roots.iter().cloned())
+            .collect();
+        // Only ask for the sysroot when no other root matches
+        if !roots.iter().any(|root| under_root(path, root)) {
+            roots = self
+                .sysroot_library()
+                .into_iter()
+                .map(Path::to_path_buf)
+                .collect();
+        }
+        roots.iter().any(|root| under_root(path, root))
+    }
+
+    /// Returns existing workspace files a missing `path` may have meant: files with
+    /// the same name, or else the entries of its nearest existing directory.
+    fn suggestions(&self, path: &Path) -> Vec<String> {
+        let Some(name) = path.file_name() else {
+            return Vec::new();
+        };
+        let mut found = Vec::new();
+        let mut pending = vec![self.workspace_root.clone()];
+        let mut visited = 0;
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.filter_map(Result::ok) {
+                visited += 1;
+                if visited > MAX_SUGGESTION_SCAN {
+                    break;
+                }
+                let entry_path = entry.path();
+                let entry_name = entry.file_name();
+                if entry_path.is_dir() {
+                    let hidden = entry_name.to_string_lossy().starts_with('.');
+                    if !hidden && entry_name != "target" {
+                        pending.push(entry_path);
+                    }
+                } else if entry_name.eq_ignore_ascii_case(name) {
+                    found.push(self.label(&entry_path));
+                }
+            }
+        }
+        if found.is_empty()
+            && let Some(dir) = path.ancestors().skip(1).find(|dir| dir.is_dir())
+            && self.is_allowed(dir)
+            && let Ok(entries) = std::fs::read_dir(dir)
+        {
+            found = entries
+                .filter_map(Result::ok)
+                .map(|entry| self.label(&entry.path()))
+                .collect();
+        }
+        found.sort();
+        found.truncate(MAX_SUGGESTIONS);
+        found
+    }
+
+    /// Returns the library directory of the sysroot, asking `rustc` if no standard
+    /// library path was rendered yet.
+    fn sysroot_library(&self) -> Option<&Path> {
+        self.sysroot_library
+            .get_or_init(|| {
+                let output = Command::new("rustc")
+                    .args(["--print", "sysroot"])
+                    .output()
+                    .ok()?;
+                let sysroot = String::from_utf8(output.stdout).ok()?;
+                let mut library = PathBuf::from(sysroot.trim()).join("lib");
+                library.extend(SYSROOT_LIBRARY);
+                Some(library)
+            })
+            .as_deref()
+    }
+}
+
+/// Renders suggestions for a missing file as a sentence to append to the error.
+fn format_suggestions(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!("; did you mean {}?", suggestions.join(", "))
+    }
+}
+
+/// Returns whether `path` is `root` or lies below it, comparing real paths where they
+/// exist.
+fn under_root(path: &Path, root: &Path) -> bool {
+    path.starts_with(root)
+        || root
+            .canonicalize()
+            .is_ok_and(|real_root| path.starts_with(real_root))
+}
+
+/// Removes `.` components and folds `..` components into their parent, without
+/// touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Joins the components of a relative path with `/`.
+fn join_components(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Splits a registry directory name such as `tokio-1.48.0` into crate name and version.
+///
+/// Crate names may contain `-` followed by digits (`sha-1`), so the version starts at
+/// the first `-` followed by a full `major.minor.patch` version.
+fn split_crate_dir(dir: &str) -> Option<(&str, &str)> {
+    dir.match_indices('-')
+        .map(|(index, _)| (&dir[..index], &dir[index + 1..]))
+        .find(|(name, version)| !name.is_empty() && is_version(version))
+}
+
+/// Returns whether `text` starts with a `major.minor.patch` version.
+fn is_version(text: &str) -> bool {
+    let core = text.split(['-', '+']).next().unwrap_or_default();
+    let parts: Vec<_> = core.split('.').collect();
+    parts.len() == 3
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels() -> PathLabels {
+        PathLabels::with_dirs(
+            PathBuf::from("/home/alice/project"),
+            Some(PathBuf::from("/home/alice")),
+            Some(PathBuf::from("/home/alice/.cargo")),
+        )
+    }
+
+    #[test]
+    fn test_label() {
+        let labels = labels();
+        assert_eq!(
+            labels.label(Path::new("/home/alice/project/src/main.rs")),
+            "src/main.rs"
+        );
+        assert_eq!(
+            labels.label(Path::new(
+                "/home/alice/.cargo/registry/src/index.crates.io-6f17d22bba15001f/tokio-1.48.0/src/net/tcp/stream.rs"
+            )),
+            "tokio@1.48.0:src/net/tcp/stream.rs"
+        );
+        assert_eq!(
+            labels.label(Path::new(
+                "/home/alice/.cargo/registry/src/index.crates.io-6f17d22bba15001f/sha-1-0.10.1/src/lib.rs"
+            )),
+            "sha-1@0.10.1:src/lib.rs"
+        );
+        assert_eq!(
+            labels.label(Path::new(
+                "/home/alice/.rustup/toolchains/stable-x86_64-unknown-linux-gnu/lib/rustlib/src/rust/library/core/src/option.rs"
+            )),
+            "std:core/src/option.rs"
+        );
+        assert_eq!(
+            labels.label(Path::new(
+                "/home/alice/.cargo/git/checkouts/foo-1a2b/abc/src/lib.rs"
+            )),
+            "~/.cargo/git/checkouts/foo-1a2b/abc/src/lib.rs"
+        );
+        assert_eq!(labels.label(Path::new("/opt/lib.rs")), "/opt/lib.rs");
+    }
+
+    #[test]
+    fn test_resolve() {
+        let labels = labels();
+        assert_eq!(
+            labels.resolve("~/notes/todo.rs"),
+            Some(PathBuf::from("/home/alice/notes/todo.rs"))
+        );
+        assert_eq!(labels.resolve("src/main.rs"), None);
+        assert_eq!(labels.resolve("/home/alice/project/src/main.rs"), None);
+        assert_eq!(labels.resolve("C:/project/src/main.rs"), None);
+
+        // The sysroot is learned from rendered standard library paths
+        labels.label(Path::new(
+            "/toolchain/lib/rustlib/src/rust/library/core/src/option.rs",
+        ));
+        assert_eq!(
+            labels.resolve("std:core/src/option.rs"),
+            Some(PathBuf::from(
+                "/toolchain/lib/rustlib/src/rust/library/core/src/option.rs"
+            ))
+        );
+
+        // Dependencies resolve through the registry's index directories
+        let cargo_home = tempfile::tempdir().unwrap();
+        let crate_dir = cargo_home
+            .path()
+            .join("registry/src/index.crates.io-1234/tokio-1.48.0");
+        std::fs::create_dir_all(&crate_dir).unwrap();
+        let labels = PathLabels::with_dirs(
+            PathBuf::from("/project"),
+            None,
+            Some(cargo_home.path().to_path_buf()),
+        );
+        assert_eq!(
+            labels.resolve("tokio@1.48.0:src/lib.rs"),
+            Some(crate_dir.join("src/lib.rs"))
+        );
+        assert_eq!(labels.resolve("tokio@9.9.9:src/lib.rs"), None);
+        assert_eq!(
+            labels.label(&crate_dir.join("src/lib.rs")),
+            "tokio@1.48.0:src/lib.rs"
+        );
+    }
+
+    #[test]
+    fn test_resolve_input() {
+        let workspace = tempfile::tempdir().unwrap();
+        let root = workspace.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let secret = outside.path().canonicalize().unwrap().join("secret.rs");
+        std::fs::write(&secret, "").unwrap();
+        let labels = PathLabels::with_dirs(root.clone(), None, None);
+
+        let main_rs = root.join("src/main.rs");
+        assert_eq!(labels.resolve_input("src/main.rs").unwrap(), main_rs);
+        assert_eq!(
+            labels.resolve_input("./src/../src/main.rs").unwrap(),
+            main_rs
+        );
+        assert_eq!(
+            labels.resolve_input(main_rs.to_str().unwrap()).unwrap(),
+            main_rs
+        );
+        let uri = Url::from_file_path(&main_rs).unwrap();
+        assert_eq!(labels.resolve_input(uri.as_str()).unwrap(), main_rs);
+        assert!(matches!(
+            labels.resolve_input("file://remote/src/main.rs"),
+            Err(PathError::InvalidUri(_))
+        ));
+
+        // Missing files suggest same-named files, or else their directory's entries
+        let error = labels.resolve_input("lib/main.rs").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "file not found: lib/main.rs; did you mean src/main.rs?"
+        );
+        let error = labels.resolve_input("src/mian.rs").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "file not found: src/mian.rs; did you mean src/main.rs?"
+        );
+        assert!(matches!(
+            labels.resolve_input("src"),
+            Err(PathError::NotFound { .. })
+        ));
+
+        // Paths outside the roots are refused, also through `..` and symlinks
+        let secret_text = secret.to_str().unwrap();
+        assert!(matches!(
+            labels.resolve_input(secret_text),
+            Err(PathError::OutsideRoots(_))
+        ));
+        assert!(matches!(
+            labels.resolve_input("../../../../../../etc/shadow"),
+            Err(PathError::OutsideRoots(_))
+        ));
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&secret, root.join("src/link.rs")).unwrap();
+            assert!(matches!(
+                labels.resolve_input("src/link.rs"),
+                Err(PathError::OutsideRoots(_))
+            ));
+        }
+
+        let labels = PathLabels::with_dirs(root.clone(), None, None)
+            .with_allowed_roots([outside.path().to_path_buf()]);
+        assert_eq!(labels.resolve_input(secret_text).unwrap(), secret);
+        let labels = PathLabels::with_dirs(root.clone(), None, None).allow_any(true);
+        assert_eq!(labels.resolve_input(secret_text).unwrap(), secret);
+
+        // Of Cargo's home, only the dependency sources are readable
+        let home = outside.path().canonicalize().unwrap();
+        let cargo_home = home.join(".cargo");
+        let dependency = cargo_home.join("registry/src/index.crates.io-1234/foo-1.0.0/src/lib.rs");
+        std::fs::create_dir_all(dependency.parent().unwrap()).unwrap();
+        std::fs::write(&dependency, "").unwrap();
+        std::fs::write(cargo_home.join("credentials.toml"), "").unwrap();
+        std::fs::write(cargo_home.join("config.toml"), "").unwrap();
+        let labels = PathLabels::with_dirs(root, Some(home), Some(cargo_home));
+        assert_eq!(
+            labels.resolve_input("foo@1.0.0:src/lib.rs").unwrap(),
+            dependency
+        );
+        for config in ["~/.cargo/credentials.toml", "~/.cargo/config.toml"] {
+            assert!(
+                matches!(
+                    labels.resolve_input(config),
+                    Err(PathError::OutsideRoots(_))
+                ),
+                "{config} should be refused"
+            );
+        }
+    }
+}
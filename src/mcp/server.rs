@@ -3,28 +3,54 @@
 //! This module contains the `KadabraRunes` struct that implements the MCP server
 //! with code navigation tools powered by the Language Server Protocol.
 #[allow(dead_code)]
+use std::collections::{HashMap, HashSet};
 use std::fmt::Write as _;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use super::symbol_path::{
+    crate_name_for, document_symbol_chain, impl_trait_and_self_type, is_symbol_path,
+    parse_symbol_path, symbol_path_matches, symbol_path_segment,
+};
+use crate::error::LspError;
 use crate::lsp::client::LspClient;
-use crate::lsp::types::{from_lsp_position, symbol_kind_to_string};
-use lsp_types::{DocumentSymbol, DocumentSymbolResponse, GotoDefinitionResponse};
+use crate::lsp::ext::{ExternalDocsPair, Runnable, SyntaxTreeNode};
+use crate::lsp::types::{
+    byte_to_utf16_offset, completion_kind_to_string, diagnostic_severity_to_string,
+    from_lsp_position, highlight_kind_to_string, path_to_url, symbol_kind_to_string,
+    to_lsp_position, utf16_offset_to_byte,
+};
+use lsp_types::{
+    CallHierarchyItem, Diagnostic, DiagnosticSeverity, DocumentChangeOperation, DocumentChanges,
+    DocumentSymbol, DocumentSymbolResponse, GotoDefinitionResponse, NumberOrString, OneOf,
+    Position, Range, SymbolKind, TextEdit, TypeHierarchyItem, Url, WorkspaceEdit,
+};
+use rmcp::handler::server::common::schema_for_output;
 use rmcp::handler::server::tool::ToolRouter;
 use rmcp::{
     ErrorData as McpError, ServerHandler,
     handler::server::wrapper::Parameters,
     model::{
-        CallToolResult, Content, ErrorCode, Implementation, ProtocolVersion, ServerCapabilities,
-        ServerInfo,
+        CallToolResult, Content, ErrorCode, Implementation, JsonObject, ProtocolVersion,
+        ServerCapabilities, ServerInfo,
     },
     tool, tool_handler, tool_router,
 };
+use schemars::JsonSchema;
+use serde::Serialize;
 
+use super::paths::PathLabels;
 use super::tools::{
-    DocumentSymbolsParams, FindReferencesParams, HoverParams, ImplementationsParams,
-    IncomingCallsParams, OutgoingCallsParams, PositionParams, SymbolNameParams, SymbolQuery,
-    TypeDefinitionParams, WorkspaceSymbolsParams,
+    AnalyzerStatusParams, CallInfo, CallsResult, CheckWorkspaceParams, CompletionsParams,
+    ContextMode, CrateDependency, CrateGraph, CrateGraphParams, CrateNode, DiagnosticsParams,
+    DocumentHighlightsParams, DocumentSymbolsParams, EnclosingItem, ExpandMacroParams,
+    ExternalDocsParams, FileCount, FileGroup, FilterParams, FindReferencesParams, GotoParams,
+    GraphFormat, HoverParams, HoverResult, ImplementationsParams, IncomingCallsParams,
+    InlayHintsParams, ItemGroup, ItemViewParams, LocationContext, LocationWithContext,
+    LocationsResult, ModuleTreeParams, OutgoingCallsParams, PageInfo, PageParams, PositionParams,
+    ReferenceSummary, RelatedTestsParams, RunnablesParams, SsrPreviewParams, SsrScope, SymbolInfo,
+    SymbolNameParams, SymbolQuery, SymbolsResult, TypeDefinitionParams, TypeHierarchyParams,
+    WorkspaceSymbolsParams,
 };
 
 /// MCP server for semantic code navigation.
@@ -35,6 +61,8 @@ use super::tools::{
 pub struct KadabraRunes {
     /// Root directory of the workspace to navigate.
     workspace_root: PathBuf,
+    /// Short path forms for output and input.
+    paths: Arc<PathLabels>,
     /// LSP client for semantic code navigation.
     lsp_client: Arc<LspClient>,
     #[allow(dead_code)]
@@ -51,333 +79,3979 @@ impl KadabraRunes {
     #[allow(dead_code)]
     pub fn new(workspace_root: PathBuf, lsp_client: Arc<LspClient>) -> Self {
         Self {
+            paths: Arc::new(PathLabels::new(workspace_root.clone())),
             workspace_root,
             lsp_client,
             tool_router: Self::tool_router(),
         }
     }
 
+    /// Replaces the path labels, e.g. to allow reading files outside the workspace.
+    #[must_use]
+    pub fn with_path_labels(mut self, paths: PathLabels) -> Self {
+        self.paths = Arc::new(paths);
+        self
+    }
+
     /// Returns the workspace root path.
     #[allow(dead_code)]
     pub fn workspace_root(&self) -> &PathBuf {
         &self.workspace_root
     }
-}
-
-// Helper functions for formatting LSP responses
-// Note: These are called by the #[tool_router] macro-generated code,
-// but the compiler's dead code analysis doesn't see through macros.
-
-/// Reads context lines around a specific line in a file.
-///
-/// Returns a formatted string with line numbers and a marker for the target line.
-#[allow(dead_code)]
-fn read_context_lines(path: &Path, line: u32, context: usize) -> Result<String, std::io::Error> {
-    let file_content = std::fs::read_to_string(path)?;
-    let lines: Vec<_> = file_content.lines().collect();
-    let line_idx = line.saturating_sub(1) as usize; // Convert to 0-indexed
-
-    let start = line_idx.saturating_sub(context);
-    let end = (line_idx + context + 1).min(lines.len());
-
-    let mut result = String::new();
-    for (idx, line_text) in lines[start..end].iter().enumerate() {
-        let line_num = start + idx + 1;
-        let marker = if line_num == (line_idx + 1) { ">" } else { " " };
-        let _ = writeln!(result, "{marker} {line_num:4} | {line_text}");
-    }
-    Ok(result)
-}
 
-/// Formats a single LSP location with source context.
-#[allow(dead_code)]
-fn format_location(loc: &lsp_types::Location, context_lines: usize) -> Result<String, McpError> {
-    let file_path = loc
-        .uri
-        .to_file_path()
-        .map_err(|()| McpError::new(ErrorCode::INTERNAL_ERROR, "invalid file URI", None))?;
+    /// Expands a type hierarchy item in one direction up to `depth` levels.
+    ///
+    /// Writes one line per related type, indented by its distance from `root`.
+    /// Types already expanded are not expanded again, so cyclic hierarchies terminate.
+    /// With `declared`, related types come from declarations and implementations
+    /// instead of the server's type hierarchy requests.
+    async fn expand_type_hierarchy(
+        &self,
+        root: &TypeHierarchyItem,
+        direction: HierarchyDirection,
+        depth: u32,
+        declared: bool,
+        out: &mut String,
+    ) -> Result<(), McpError> {
+        let mut visited = HashSet::new();
+        let mut found_any = false;
+        let mut stack = vec![(root.clone(), 0_usize)];
 
-    let (line, column) = from_lsp_position(loc.range.start);
+        while let Some((item, level)) = stack.pop() {
+            if level > 0 {
+                found_any = true;
+                let _ = writeln!(
+                    out,
+                    "{}{}",
+                    "  ".repeat(level),
+                    format_type_hierarchy_item(&self.paths, &item)
+                );
+            }
 
-    let context = read_context_lines(&file_path, line, context_lines).map_err(|e| {
-        McpError::new(
-            ErrorCode::INTERNAL_ERROR,
-            format!("failed to read file: {e}"),
-            None,
-        )
-    })?;
+            let key = lsp_types::Location {
+                uri: item.uri.clone(),
+                range: item.selection_range,
+            };
+            if level >= depth as usize || !visited.insert(key) {
+                continue;
+            }
 
-    Ok(format!(
-        "{}:{}:{}\n{}",
-        file_path.display(),
-        line,
-        column,
-        context
-    ))
-}
+            let related = if declared {
+                self.declared_related_types(&item, direction).await?
+            } else {
+                match direction {
+                    HierarchyDirection::Supertypes => self.lsp_client.supertypes(item).await,
+                    HierarchyDirection::Subtypes => self.lsp_client.subtypes(item).await,
+                }
+                .map_err(|e| {
+                    McpError::new(
+                        ErrorCode::INTERNAL_ERROR,
+                        format!("type_hierarchy failed: {e}"),
+                        None,
+                    )
+                })?
+            };
 
-/// Searches for a symbol by exact name in document symbols.
-///
-/// Returns (line, column) if found, None otherwise.
-#[allow(dead_code)]
-fn find_symbol_in_document(
-    doc_symbols: &DocumentSymbolResponse,
-    symbol_name: &str,
-) -> Option<(u32, u32)> {
-    match doc_symbols {
-        DocumentSymbolResponse::Flat(symbols) => {
-            // Search flat symbol list
-            symbols
-                .iter()
-                .find(|sym| sym.name == symbol_name)
-                .map(|sym| from_lsp_position(sym.location.range.start))
-        }
-        DocumentSymbolResponse::Nested(symbols) => {
-            // Search nested symbol hierarchy recursively
-            find_in_nested_symbols(symbols, symbol_name)
+            // Push in reverse so children are written in the order the server returned them
+            for child in related.into_iter().rev() {
+                stack.push((child, level + 1));
+            }
         }
-    }
-}
 
-/// Recursively searches nested `DocumentSymbol` tree for exact match.
-fn find_in_nested_symbols(symbols: &[DocumentSymbol], symbol_name: &str) -> Option<(u32, u32)> {
-    for symbol in symbols {
-        // Check current symbol
-        if symbol.name == symbol_name {
-            return Some(from_lsp_position(symbol.selection_range.start));
+        if !found_any {
+            out.push_str("  (none)\n");
         }
 
-        // Recursively check children
-        if let Some(children) = &symbol.children
-            && let Some(result) = find_in_nested_symbols(children, symbol_name)
-        {
-            return Some(result);
-        }
+        Ok(())
     }
-    None
-}
 
-/// Formats multiple LSP locations with context.
-#[allow(dead_code)]
-fn format_locations(
-    locations: &[lsp_types::Location],
-    context_lines: usize,
-) -> Result<String, McpError> {
-    if locations.is_empty() {
-        return Ok("No results found.".to_string());
+    /// Returns the item declared around a location as a type hierarchy item.
+    async fn declared_type_item(
+        &self,
+        uri: &Url,
+        position: Position,
+    ) -> Result<Option<TypeHierarchyItem>, McpError> {
+        let Ok(path) = uri.to_file_path() else {
+            return Ok(None);
+        };
+        let symbols = self.nested_document_symbols(&path).await?;
+        Ok(
+            innermost_item(&symbols, position)
+                .map(|symbol| symbol_type_hierarchy_item(uri, symbol)),
+        )
     }
 
-    let results: Result<Vec<String>, McpError> = locations
-        .iter()
-        .map(|loc| format_location(loc, context_lines))
-        .collect();
-
-    Ok(results?.join("\n\n---\n\n"))
-}
+    /// Returns the direct supertraits or implementations of a trait without the server's
+    /// type hierarchy support.
+    ///
+    /// Supertraits are the bounds in the trait's declaration, resolved with
+    /// `textDocument/definition`; implementations come from `textDocument/implementation`.
+    /// Other kinds of items have no related types.
+    async fn declared_related_types(
+        &self,
+        item: &TypeHierarchyItem,
+        direction: HierarchyDirection,
+    ) -> Result<Vec<TypeHierarchyItem>, McpError> {
+        if item.kind != SymbolKind::INTERFACE {
+            return Ok(Vec::new());
+        }
+        let Ok(path) = item.uri.to_file_path() else {
+            return Ok(Vec::new());
+        };
+        let request_failed = |e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("type_hierarchy failed: {e}"),
+                None,
+            )
+        };
 
-/// Converts `GotoDefinitionResponse` to a list of locations.
-#[allow(dead_code)]
-fn goto_response_to_locations(response: GotoDefinitionResponse) -> Vec<lsp_types::Location> {
-    match response {
-        GotoDefinitionResponse::Scalar(loc) => vec![loc],
-        GotoDefinitionResponse::Array(locs) => locs,
-        GotoDefinitionResponse::Link(links) => links
-            .into_iter()
-            .map(|link| lsp_types::Location {
-                uri: link.target_uri,
-                range: link.target_range,
-            })
-            .collect(),
-    }
-}
+        let mut targets = Vec::new();
+        match direction {
+            HierarchyDirection::Supertypes => {
+                let Ok(content) = std::fs::read_to_string(&path) else {
+                    return Ok(Vec::new());
+                };
+                let lines: Vec<&str> = content.split('\n').collect();
+                let header_start = position_to_offset(&lines, 0, item.selection_range.end);
+                for offset in supertrait_offsets(&content[header_start..]) {
+                    let (line, column) =
+                        from_lsp_position(offset_to_position(&content, header_start + offset));
+                    let response = self
+                        .lsp_client
+                        .goto_definition(&path, line, column)
+                        .await
+                        .map_err(request_failed)?;
+                    targets.extend(goto_response_to_locations(response).into_iter().take(1));
+                }
+            }
+            HierarchyDirection::Subtypes => {
+                let (line, column) = from_lsp_position(item.selection_range.start);
+                let response = self
+                    .lsp_client
+                    .implementations(&path, line, column)
+                    .await
+                    .map_err(request_failed)?;
+                targets = goto_response_to_locations(response);
+            }
+        }
 
-/// Extracts markdown text from `MarkupContent` or string.
-#[allow(dead_code)]
-fn extract_hover_text(content: lsp_types::HoverContents) -> String {
-    match content {
-        lsp_types::HoverContents::Scalar(marked_string) => match marked_string {
-            lsp_types::MarkedString::String(s) => s,
-            lsp_types::MarkedString::LanguageString(ls) => {
-                format!("```{}\n{}\n```", ls.language, ls.value)
+        let mut related = Vec::new();
+        for target in targets {
+            // Items outside the workspace may have no document symbols to name them
+            if let Ok(Some(item)) = self
+                .declared_type_item(&target.uri, target.range.start)
+                .await
+            {
+                related.push(item);
             }
-        },
-        lsp_types::HoverContents::Array(marked_strings) => marked_strings
-            .into_iter()
-            .map(|ms| match ms {
-                lsp_types::MarkedString::String(s) => s,
-                lsp_types::MarkedString::LanguageString(ls) => {
-                    format!("```{}\n{}\n```", ls.language, ls.value)
-                }
-            })
-            .collect::<Vec<_>>()
-            .join("\n\n"),
-        lsp_types::HoverContents::Markup(markup) => markup.value,
+        }
+        Ok(related)
     }
-}
 
-/// Formats document symbols recursively.
-#[allow(dead_code)]
-fn format_document_symbols(symbols: &[lsp_types::DocumentSymbol], indent: usize) -> String {
-    let mut result = String::new();
-    let indent_str = "  ".repeat(indent);
+    /// Walks up from a position to its crate root using `experimental/parentModule`.
+    ///
+    /// Returns the `mod` declarations from the crate root down to the module containing
+    /// the position. An empty list means the position is in a crate root.
+    async fn module_ancestors(
+        &self,
+        path: &Path,
+        line: u32,
+        column: u32,
+    ) -> Result<Vec<lsp_types::Location>, McpError> {
+        let mut ancestors: Vec<lsp_types::Location> = Vec::new();
+        let mut query = (path.to_path_buf(), line, column);
 
-    for symbol in symbols {
-        let kind = symbol_kind_to_string(symbol.kind);
-        let (line, _) = from_lsp_position(symbol.selection_range.start);
-        let _ = writeln!(
-            result,
-            "{}[{}] {} (line {})",
-            indent_str, kind, symbol.name, line
-        );
+        // Bounded so a misbehaving server cannot loop forever
+        while ancestors.len() < MAX_MODULE_NESTING {
+            let response = self
+                .lsp_client
+                .parent_module(&query.0, query.1, query.2)
+                .await
+                .map_err(|e| {
+                    McpError::new(
+                        ErrorCode::INTERNAL_ERROR,
+                        format!("module_tree failed: {e}"),
+                        None,
+                    )
+                })?;
 
-        if let Some(children) = &symbol.children {
-            result.push_str(&format_document_symbols(children, indent + 1));
+            // Crate roots link to the package's Cargo.toml rather than a `mod` declaration
+            let Some(declaration) = response
+                .map(goto_response_to_locations)
+                .and_then(|locations| locations.into_iter().next())
+                .filter(|location| !location.uri.path().ends_with("/Cargo.toml"))
+            else {
+                break;
+            };
+            let Ok(parent_path) = declaration.uri.to_file_path() else {
+                break;
+            };
+            if ancestors.contains(&declaration) {
+                break;
+            }
+
+            let (line, column) = from_lsp_position(declaration.range.start);
+            query = (parent_path, line, column);
+            ancestors.push(declaration);
         }
+
+        ancestors.reverse();
+        Ok(ancestors)
     }
 
-    result
-}
+    /// Writes the submodules below a position using `experimental/childModules`.
+    ///
+    /// Writes one line per submodule, indented by `indent` plus its depth below the
+    /// starting module, and descends at most `depth` levels.
+    async fn expand_module_tree(
+        &self,
+        path: &Path,
+        line: u32,
+        column: u32,
+        indent: usize,
+        depth: u32,
+        out: &mut String,
+    ) -> Result<(), McpError> {
+        let mut visited = HashSet::new();
+        let mut stack: Vec<(lsp_types::Location, usize)> = Vec::new();
 
-/// Formats flat symbol information.
-#[allow(dead_code)]
-fn format_symbol_information(symbols: &[lsp_types::SymbolInformation]) -> String {
-    let mut result = String::new();
+        let mut query = Some((path.to_path_buf(), line, column, 0_usize));
+        loop {
+            if let Some((path, line, column, level)) = query.take()
+                && level < depth as usize
+            {
+                let children = self.child_module_declarations(&path, line, column).await?;
+                // Push in reverse so submodules are written in declaration order
+                for child in children.into_iter().rev() {
+                    stack.push((child, level + 1));
+                }
+            }
 
-    for symbol in symbols {
-        let kind = symbol_kind_to_string(symbol.kind);
-        let file_path = symbol.location.uri.to_file_path().map_or_else(
-            |()| symbol.location.uri.to_string(),
-            |p| p.display().to_string(),
-        );
-        let (line, _) = from_lsp_position(symbol.location.range.start);
+            let Some((declaration, level)) = stack.pop() else {
+                break;
+            };
+            if !visited.insert(declaration.clone()) {
+                continue;
+            }
+            let Ok(declaration_path) = declaration.uri.to_file_path() else {
+                continue;
+            };
+            let (line, column) = from_lsp_position(declaration.range.start);
 
-        let container = symbol
-            .container_name
-            .as_ref()
-            .map_or_else(Default::default, |c| format!(" (in {c})"));
+            // Out-of-line modules resolve to their own file; inline ones to themselves
+            let module_path = self
+                .lsp_client
+                .goto_definition(&declaration_path, line, column)
+                .await
+                .ok()
+                .and_then(|response| goto_response_to_locations(response).into_iter().next())
+                .and_then(|location| location.uri.to_file_path().ok())
+                .unwrap_or_else(|| declaration_path.clone());
 
-        let _ = writeln!(
-            result,
-            "[{}] {}{} - {}:{}",
-            kind, symbol.name, container, file_path, line
-        );
-    }
+            let _ = writeln!(
+                out,
+                "{}{}",
+                "  ".repeat(indent + level),
+                format_module_line(&self.paths, &declaration, &module_path)
+            );
+            query = Some((declaration_path, line, column, level));
+        }
 
-    result
-}
+        Ok(())
+    }
 
-/// Tool implementations for `KadabraRunes`.
-#[tool_router]
-impl KadabraRunes {
-    /// Jump to the definition of a symbol at a given position or by name.
-    #[tool(
-        description = "Jump to where a symbol is defined. Essential for tracing imports and understanding implementations."
-    )]
-    pub async fn goto_definition(
+    /// Returns the `mod` declarations of the submodules of the module at a position.
+    async fn child_module_declarations(
         &self,
-        Parameters(params): Parameters<PositionParams>,
-    ) -> Result<CallToolResult, McpError> {
-        // Extract position from params
-        let file_path = self.workspace_root.join(&params.file_path);
-        let line = params.line;
-        let column = params.column;
-
-        // Ensure the document is open
-        self.lsp_client.did_open(&file_path).await.map_err(|e| {
-            McpError::new(
-                ErrorCode::INTERNAL_ERROR,
-                format!("failed to open document: {e}"),
-                None,
-            )
-        })?;
-
-        // Call LSP client
+        path: &Path,
+        line: u32,
+        column: u32,
+    ) -> Result<Vec<lsp_types::Location>, McpError> {
         let response = self
             .lsp_client
-            .goto_definition(&file_path, line, column)
+            .child_modules(path, line, column)
             .await
             .map_err(|e| {
                 McpError::new(
                     ErrorCode::INTERNAL_ERROR,
-                    format!("goto_definition failed: {e}"),
+                    format!("module_tree failed: {e}"),
                     None,
                 )
             })?;
+        Ok(response.map(goto_response_to_locations).unwrap_or_default())
+    }
 
-        // Convert response to locations
-        let locations = goto_response_to_locations(response);
+    /// Formats diagnostics grouped by file and enclosing symbol, one file per block.
+    async fn format_grouped_diagnostics(&self, files: Vec<(PathBuf, Vec<Diagnostic>)>) -> String {
+        let mut formatted = String::new();
+        for (path, mut diagnostics) in files {
+            diagnostics.sort_by_key(|d| (d.range.start.line, d.range.start.character));
+
+            // Symbols are best effort; diagnostics are still listed without them
+            let symbols = match self.lsp_client.did_open(&path).await {
+                Ok(()) => self.lsp_client.document_symbols(&path).await.ok(),
+                Err(_) => None,
+            };
+            let mut groups: Vec<(Option<String>, Vec<&Diagnostic>)> = Vec::new();
+            for diagnostic in &diagnostics {
+                let symbol = symbols
+                    .as_ref()
+                    .and_then(|symbols| enclosing_symbol(symbols, diagnostic.range.start));
+                match groups.iter_mut().find(|(s, _)| *s == symbol) {
+                    Some((_, group)) => group.push(diagnostic),
+                    None => groups.push((symbol, vec![diagnostic])),
+                }
+            }
 
-        // Format locations with context
-        let formatted = format_locations(locations.as_slice(), 2)?;
+            let _ = writeln!(formatted, "\n{}", self.paths.label(&path));
+            for (symbol, group) in groups {
+                let _ = writeln!(
+                    formatted,
+                    "  {}",
+                    symbol.as_deref().unwrap_or("(top level)")
+                );
+                for diagnostic in group {
+                    let _ = writeln!(formatted, "    {}", format_diagnostic(diagnostic));
+                }
+            }
+        }
 
-        Ok(CallToolResult::success(vec![Content::text(formatted)]))
+        formatted
     }
 
-    /// Find all references to a symbol in the workspace.
-    #[tool(
-        description = "Find all usages of a symbol. Reveals dependencies, call sites, and impact of changes."
-    )]
-    async fn find_references(
+    /// Resolves a symbol query to a file position, looking names up with `workspace/symbol`
+    /// and resolving Rust paths such as `Type::method`.
+    async fn resolve_symbol_query(
         &self,
-        Parameters(params): Parameters<FindReferencesParams>,
-    ) -> Result<CallToolResult, McpError> {
-        // Extract position from params
-        let (file_path, line, column) = match &params.query {
-            SymbolQuery::Position(pos) => (
-                self.workspace_root.join(&pos.file_path),
-                pos.line,
-                pos.column,
-            ),
-            SymbolQuery::Name(SymbolNameParams { symbol, .. }) => {
-                return Err(McpError::new(
-                    ErrorCode::INVALID_PARAMS,
-                    format!(
-                        "Symbol name queries not yet implemented. Use workspace_symbols to find '{symbol}' first, then use position-based query."
-                    ),
-                    None,
-                ));
+        query: &SymbolQuery,
+    ) -> Result<ResolvedPosition, McpError> {
+        let (file_path, line, column) = match query {
+            SymbolQuery::Position(pos) => return self.resolve_position(pos).await,
+            SymbolQuery::Name(name) if is_symbol_path(&name.symbol) => {
+                self.resolve_symbol_path(&name.symbol).await?
             }
+            SymbolQuery::Name(name) => return self.resolve_symbol_name(name).await,
         };
+        Ok(ResolvedPosition {
+            file_path,
+            line,
+            column,
+            note: None,
+        })
+    }
 
-        // Ensure the document is open
-        self.lsp_client.did_open(&file_path).await.map_err(|e| {
-            McpError::new(
-                ErrorCode::INTERNAL_ERROR,
-                format!("failed to open document: {e}"),
-                None,
-            )
-        })?;
+    /// Resolves a symbol name to the position of its declaration.
+    ///
+    /// Workspace symbols (narrowed to `file_path` when given) are searched first, then
+    /// the document symbols of `file_path`. Exact matches win over fuzzy ones; a fuzzy
+    /// pick is named in the note.
+    async fn resolve_symbol_name(
+        &self,
+        params: &SymbolNameParams,
+    ) -> Result<ResolvedPosition, McpError> {
+        let file_path = params
+            .file_path
+            .as_deref()
+            .map(|path| self.input_path(path))
+            .transpose()?;
 
-        // Call LSP client
-        let locations = self
+        let symbols = self
             .lsp_client
-            .find_references(&file_path, line, column, params.include_declaration)
+            .workspace_symbols(&params.symbol)
             .await
             .map_err(|e| {
                 McpError::new(
                     ErrorCode::INTERNAL_ERROR,
-                    format!("find_references failed: {e}"),
+                    format!("workspace_symbols failed: {e}"),
                     None,
                 )
             })?;
+        let (exact, fuzzy): (Vec<_>, Vec<_>) = symbols
+            .into_iter()
+            .filter(|symbol| {
+                file_path.as_ref().is_none_or(|file_path| {
+                    symbol.location.uri.to_file_path().ok().as_ref() == Some(file_path)
+                })
+            })
+            .partition(|symbol| symbol.name == params.symbol);
+        if !exact.is_empty() {
+            return self
+                .pick_symbol_candidate(&params.symbol, exact, false)
+                .await;
+        }
+
+        // Document symbols also cover items that workspace symbol search leaves out
+        if let Some(file_path) = &file_path {
+            self.lsp_client.did_open(file_path).await.map_err(|e| {
+                McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("failed to open document: {e}"),
+                    None,
+                )
+            })?;
+            let symbols = self
+                .lsp_client
+                .document_symbols(file_path)
+                .await
+                .map_err(|e| {
+                    McpError::new(
+                        ErrorCode::INTERNAL_ERROR,
+                        format!("document_symbols failed: {e}"),
+                        None,
+                    )
+                })?;
+            if let Some((line, column)) = find_symbol_in_document(&symbols, &params.symbol) {
+                return Ok(ResolvedPosition {
+                    file_path: file_path.clone(),
+                    line,
+                    column,
+                    note: None,
+                });
+            }
+        }
+
+        if !fuzzy.is_empty() {
+            return self
+                .pick_symbol_candidate(&params.symbol, fuzzy, true)
+                .await;
+        }
+
+        let scope = file_path
+            .map(|file_path| format!(" in {}", self.paths.label(&file_path)))
+            .unwrap_or_default();
+        Err(McpError::new(
+            ErrorCode::INVALID_PARAMS,
+            format!(
+                "Symbol '{}' not found{scope}. Use workspace_symbols to search for similar names.",
+                params.symbol
+            ),
+            None,
+        ))
+    }
+
+    /// Returns the position of the only distinct candidate, or an error listing them all.
+    ///
+    /// Candidates resolving to the same definition, such as re-exports, count as one.
+    /// For `fuzzy` candidates, which do not match `name` exactly, the note names the pick.
+    async fn pick_symbol_candidate(
+        &self,
+        name: &str,
+        candidates: Vec<lsp_types::SymbolInformation>,
+        fuzzy: bool,
+    ) -> Result<ResolvedPosition, McpError> {
+        let mut distinct: Vec<(lsp_types::Location, lsp_types::SymbolInformation)> = Vec::new();
+        if candidates.len() == 1 {
+            distinct.extend(candidates.into_iter().map(|c| (c.location.clone(), c)));
+        } else {
+            for candidate in candidates {
+                let definition = self.definition_of(&candidate.location).await;
+                if !distinct.iter().any(|(location, _)| *location == definition) {
+                    distinct.push((definition, candidate));
+                }
+            }
+        }
+
+        if let [(location, symbol)] = distinct.as_slice() {
+            let file_path = location.uri.to_file_path().map_err(|()| {
+                McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("symbol '{name}' is not in a local file: {}", location.uri),
+                    None,
+                )
+            })?;
+            let (line, column) = from_lsp_position(location.range.start);
+            return Ok(ResolvedPosition {
+                file_path,
+                line,
+                column,
+                note: fuzzy
+                    .then(|| format!("no exact match for `{name}`; using `{}`", symbol.name)),
+            });
+        }
+
+        let candidates: Vec<_> = distinct.into_iter().map(|(_, symbol)| symbol).collect();
+        Err(McpError::new(
+            ErrorCode::INVALID_PARAMS,
+            format!(
+                "Symbol '{name}' is ambiguous; {} candidates:\n{}Narrow the search with file_path or use a position-based query.",
+                candidates.len(),
+                format_symbol_information(&self.paths, &candidates)
+            ),
+            None,
+        ))
+    }
+
+    /// Returns the location a symbol location's definition is at, or the location itself.
+    async fn definition_of(&self, location: &lsp_types::Location) -> lsp_types::Location {
+        let Ok(path) = location.uri.to_file_path() else {
+            return location.clone();
+        };
+        let (line, column) = from_lsp_position(location.range.start);
+        if self.lsp_client.did_open(&path).await.is_err() {
+            return location.clone();
+        }
+        self.lsp_client
+            .goto_definition(&path, line, column)
+            .await
+            .ok()
+            .and_then(|response| goto_response_to_locations(response).into_iter().next())
+            .unwrap_or_else(|| location.clone())
+    }
+
+    /// Resolves a position given as a file position, a line plus token, or a Rust path.
+    ///
+    /// Columns are anchored to an identifier on the line, see [`anchor_column`].
+    async fn resolve_position(
+        &self,
+        position: &PositionParams,
+    ) -> Result<ResolvedPosition, McpError> {
+        self.resolve_position_with(position, true).await
+    }
+
+    /// Resolves a cursor position like [`Self::resolve_position`], but keeps a column
+    /// given without `symbol_text` as is: a cursor after `value.` or at the end of a
+    /// line must not snap back onto the preceding identifier.
+    async fn resolve_cursor(
+        &self,
+        position: &PositionParams,
+    ) -> Result<ResolvedPosition, McpError> {
+        self.resolve_position_with(position, false).await
+    }
+
+    /// Resolves a position, snapping a column off identifiers if `snap` is set.
+    async fn resolve_position_with(
+        &self,
+        position: &PositionParams,
+        snap: bool,
+    ) -> Result<ResolvedPosition, McpError> {
+        if let Some(symbol_path) = &position.symbol_path {
+            let (file_path, line, column) = self.resolve_symbol_path(symbol_path).await?;
+            return Ok(ResolvedPosition {
+                file_path,
+                line,
+                column,
+                note: None,
+            });
+        }
+        if position.file_path.is_empty() {
+            return Err(McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                "either file_path with line and column (or symbol_text), or symbol_path is required"
+                    .to_string(),
+                None,
+            ));
+        }
+
+        let file_path = self.input_path(&position.file_path)?;
+        let line = position.line;
+        let text = std::fs::read_to_string(&file_path)
+            .ok()
+            .and_then(|content| {
+                let index = usize::try_from(line.checked_sub(1)?).ok()?;
+                content.lines().nth(index).map(str::to_string)
+            });
+        let Some(text) = text else {
+            if position.symbol_text.is_some() {
+                return Err(McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!("line {line} not found in {}", self.paths.label(&file_path)),
+                    None,
+                ));
+            }
+            // Leave reporting unreadable files and invalid lines to the tool
+            return Ok(ResolvedPosition {
+                file_path,
+                line,
+                column: position.column,
+                note: None,
+            });
+        };
+        if !snap && position.symbol_text.is_none() {
+            return Ok(ResolvedPosition {
+                file_path,
+                line,
+                column: position.column,
+                note: None,
+            });
+        }
+
+        let (column, note) = anchor_column(
+            &text,
+            line,
+            position.column,
+            position.symbol_text.as_deref(),
+            position.occurrence,
+        )
+        .map_err(|message| McpError::new(ErrorCode::INVALID_PARAMS, message, None))?;
+        Ok(ResolvedPosition {
+            file_path,
+            line,
+            column,
+            note,
+        })
+    }
+
+    /// Resolves a Rust path such as `crate::module::Type::method` to the position of
+    /// the item's name. See [`super::symbol_path`] for the resolution rules.
+    async fn resolve_symbol_path(&self, input: &str) -> Result<(PathBuf, u32, u32), McpError> {
+        let path = parse_symbol_path(input).ok_or_else(|| {
+            McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!(
+                    "invalid symbol path '{input}'; expected e.g. `crate::module::Type::method` or `<Type as Trait>::method`"
+                ),
+                None,
+            )
+        })?;
+
+        let mut targets = match (&path.as_trait, path.segments.split_last()) {
+            (Some(as_trait), Some((item, self_type))) => {
+                let self_type = self_type.last().map_or("_", String::as_str);
+                let mut targets = Vec::new();
+                for parent in self.resolve_path_segments(as_trait).await? {
+                    if parent.kind == SymbolKind::INTERFACE {
+                        targets.extend(
+                            self.impl_member_targets(&parent, item, Some(self_type))
+                                .await?,
+                        );
+                    }
+                }
+                targets
+            }
+            _ => self.resolve_path_segments(&path.segments).await?,
+        };
+
+        let mut seen = HashSet::new();
+        targets.retain(|target| seen.insert(target.location.clone()));
+        // Inherent methods take precedence over trait methods, as in Rust's method resolution
+        if targets.len() > 1 {
+            let inherent: Vec<_> = targets
+                .iter()
+                .filter(|target| target.is_inherent_method())
+                .cloned()
+                .collect();
+            if inherent.len() == 1 {
+                targets = inherent;
+            }
+        }
+
+        match targets.as_slice() {
+            [] => Err(McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!(
+                    "Symbol path '{input}' not found. Check the module path, or use workspace_symbols to locate the item."
+                ),
+                None,
+            )),
+            [target] => {
+                let path = target.location.uri.to_file_path().map_err(|()| {
+                    McpError::new(
+                        ErrorCode::INTERNAL_ERROR,
+                        format!(
+                            "symbol '{input}' is not in a local file: {}",
+                            target.location.uri
+                        ),
+                        None,
+                    )
+                })?;
+                let (line, column) = from_lsp_position(target.location.range.start);
+                Ok((path, line, column))
+            }
+            _ => {
+                let mut candidates = String::new();
+                for target in &targets {
+                    let _ = writeln!(candidates, "{}", format_path_target(&self.paths, target));
+                }
+                Err(McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!(
+                        "Symbol path '{input}' is ambiguous; {} candidates:\n{candidates}Qualify the path further, e.g. with its module or as `<Type as Trait>::method`.",
+                        targets.len()
+                    ),
+                    None,
+                ))
+            }
+        }
+    }
+
+    /// Finds the symbols a path's segments address.
+    ///
+    /// Items in the workspace symbol index are checked against the whole path. Items
+    /// the index leaves out, such as trait methods, are found by resolving the parent
+    /// path and descending into its items.
+    async fn resolve_path_segments(
+        &self,
+        segments: &[String],
+    ) -> Result<Vec<PathTarget>, McpError> {
+        let Some((name, parent)) = segments.split_last() else {
+            return Ok(Vec::new());
+        };
+
+        let symbols = self.lsp_client.workspace_symbols(name).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("workspace_symbols failed: {e}"),
+                None,
+            )
+        })?;
+        let mut targets = Vec::new();
+        for symbol in symbols.into_iter().filter(|symbol| symbol.name == *name) {
+            let crate_name = symbol
+                .location
+                .uri
+                .to_file_path()
+                .ok()
+                .and_then(|path| crate_name_for(&path));
+            if let Some(target) = self
+                .path_target(symbol.location, symbol.kind, &symbol.name)
+                .await?
+                && symbol_path_matches(&target.segments, segments, crate_name.as_deref())
+            {
+                targets.push(target);
+            }
+        }
+        if !targets.is_empty() || parent.is_empty() {
+            return Ok(targets);
+        }
+
+        for parent in Box::pin(self.resolve_path_segments(parent)).await? {
+            targets.extend(self.child_targets(&parent, name).await?);
+        }
+        Ok(targets)
+    }
+
+    /// Builds the path target of a symbol from its module path and enclosing symbols.
+    async fn path_target(
+        &self,
+        location: lsp_types::Location,
+        kind: SymbolKind,
+        name: &str,
+    ) -> Result<Option<PathTarget>, McpError> {
+        let Ok(path) = location.uri.to_file_path() else {
+            return Ok(None);
+        };
+        let symbols = self.nested_document_symbols(&path).await?;
+
+        // The module path is best effort; servers without parentModule only see items
+        let ancestors = self.module_ancestors(&path, 1, 1).await.unwrap_or_default();
+        let mut segments: Vec<String> = ancestors
+            .iter()
+            .filter_map(|declaration| read_module_declaration(declaration).map(|(name, _)| name))
+            .collect();
+
+        let mut impl_block = None;
+        match document_symbol_chain(&symbols, location.range) {
+            Some(chain) => {
+                for symbol in &chain {
+                    segments.push(symbol_path_segment(symbol));
+                }
+                impl_block = chain
+                    .iter()
+                    .rev()
+                    .skip(1)
+                    .find(|symbol| impl_trait_and_self_type(&symbol.name).is_some())
+                    .map(|symbol| symbol.name.clone());
+            }
+            None => segments.push(name.to_string()),
+        }
+
+        Ok(Some(PathTarget {
+            location,
+            kind,
+            segments,
+            impl_block,
+        }))
+    }
+
+    /// Finds the items named `name` inside a module, trait, type or enum.
+    async fn child_targets(
+        &self,
+        parent: &PathTarget,
+        name: &str,
+    ) -> Result<Vec<PathTarget>, McpError> {
+        let Ok(path) = parent.location.uri.to_file_path() else {
+            return Ok(Vec::new());
+        };
+        let symbols = self.nested_document_symbols(&path).await?;
+        let Some(symbol) = document_symbol_chain(&symbols, parent.location.range)
+            .and_then(|chain| chain.last().copied())
+        else {
+            return Ok(Vec::new());
+        };
+        let children = symbol.children.as_deref().unwrap_or_default();
+
+        // Items of a `mod name;` declaration live at the top level of the module's file
+        if parent.kind == SymbolKind::MODULE && children.is_empty() {
+            let (line, column) = from_lsp_position(parent.location.range.start);
+            let Some(module) = self
+                .lsp_client
+                .goto_definition(&path, line, column)
+                .await
+                .ok()
+                .and_then(|response| goto_response_to_locations(response).into_iter().next())
+            else {
+                return Ok(Vec::new());
+            };
+            let Ok(module_path) = module.uri.to_file_path() else {
+                return Ok(Vec::new());
+            };
+            let module_symbols = self.nested_document_symbols(&module_path).await?;
+            return Ok(member_targets(&module.uri, &module_symbols, parent, name, None).collect());
+        }
+
+        let mut targets: Vec<PathTarget> =
+            member_targets(&parent.location.uri, children, parent, name, None).collect();
+        if matches!(
+            parent.kind,
+            SymbolKind::STRUCT | SymbolKind::ENUM | SymbolKind::CLASS
+        ) {
+            targets.extend(self.impl_member_targets(parent, name, None).await?);
+        }
+        Ok(targets)
+    }
+
+    /// Finds the items named `name` in the impl blocks of a type or trait.
+    ///
+    /// For a trait, `self_type` keeps only the impls for that type, where `_` or a
+    /// blanket impl's type parameter name matches the blanket impl.
+    async fn impl_member_targets(
+        &self,
+        parent: &PathTarget,
+        name: &str,
+        self_type: Option<&str>,
+    ) -> Result<Vec<PathTarget>, McpError> {
+        let Ok(path) = parent.location.uri.to_file_path() else {
+            return Ok(Vec::new());
+        };
+        let (line, column) = from_lsp_position(parent.location.range.start);
+        let impls = self
+            .lsp_client
+            .implementations(&path, line, column)
+            .await
+            .map_err(|e| {
+                McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("implementations failed: {e}"),
+                    None,
+                )
+            })?;
+
+        let mut targets = Vec::new();
+        for location in goto_response_to_locations(impls) {
+            let Ok(impl_path) = location.uri.to_file_path() else {
+                continue;
+            };
+            let symbols = self.nested_document_symbols(&impl_path).await?;
+            let Some(impl_symbol) = document_symbol_chain(&symbols, location.range)
+                .and_then(|chain| chain.last().copied())
+            else {
+                continue;
+            };
+            let Some((_, impl_self_type)) = impl_trait_and_self_type(&impl_symbol.name) else {
+                continue;
+            };
+            if self_type.is_some_and(|self_type| self_type != "_" && self_type != impl_self_type) {
+                continue;
+            }
+            let children = impl_symbol.children.as_deref().unwrap_or_default();
+            targets.extend(member_targets(
+                &location.uri,
+                children,
+                parent,
+                name,
+                Some(&impl_symbol.name),
+            ));
+        }
+        Ok(targets)
+    }
+
+    /// Resolves a file path given to a tool, see [`PathLabels::resolve_input`].
+    fn input_path(&self, text: &str) -> Result<PathBuf, McpError> {
+        self.paths
+            .resolve_input(text)
+            .map_err(|e| McpError::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))
+    }
+
+    /// Returns the path of a location for filtering and display: relative to the
+    /// workspace root inside the workspace, absolute outside it, and `None` for
+    /// locations that are not files.
+    fn result_path(&self, uri: &Url) -> Option<(PathBuf, bool)> {
+        let path = uri.to_file_path().ok()?;
+        match path.strip_prefix(&self.workspace_root) {
+            Ok(relative) => Some((relative.to_path_buf(), false)),
+            Err(_) => Some((path, true)),
+        }
+    }
+
+    /// Returns whether a location passes the external and path glob parts of `filter`.
+    ///
+    /// Locations that are not files count as external.
+    fn passes_path_filter(&self, uri: &Url, filter: &FilterParams) -> bool {
+        let Some((path, external)) = self.result_path(uri) else {
+            return filter.include_external && filter.include_paths.is_empty();
+        };
+        if external && !filter.include_external {
+            return false;
+        }
+        let path = path.display().to_string();
+        (filter.include_paths.is_empty()
+            || filter
+                .include_paths
+                .iter()
+                .any(|pattern| glob_matches(pattern, &path)))
+            && !filter
+                .exclude_paths
+                .iter()
+                .any(|pattern| glob_matches(pattern, &path))
+    }
+
+    /// Keeps the items whose location, given by `location_of`, passes `filter`.
+    ///
+    /// Test code is recognized by path and by the `#[cfg(test)]` modules and `#[test]`
+    /// functions around the location in the file's document symbols.
+    async fn filter_results<T>(
+        &self,
+        items: Vec<T>,
+        filter: &FilterParams,
+        location_of: impl Fn(&T) -> (&Url, Position),
+    ) -> Vec<T> {
+        let mut sources = SourceFiles::new(&self.paths);
+        let mut file_symbols: HashMap<Url, Vec<DocumentSymbol>> = HashMap::new();
+        let mut kept = Vec::with_capacity(items.len());
+        for item in items {
+            let (uri, position) = location_of(&item);
+            if !self.passes_path_filter(uri, filter) {
+                continue;
+            }
+            if !filter.include_tests
+                && let Some((relative, _)) = self.result_path(uri)
+                && let Ok(path) = uri.to_file_path()
+            {
+                if !file_symbols.contains_key(uri) {
+                    // Symbols are best effort; without them only the path is checked
+                    let symbols = self.nested_document_symbols(&path).await;
+                    file_symbols.insert(uri.clone(), symbols.unwrap_or_default());
+                }
+                let chain = item_chain(&file_symbols[uri], position);
+                if is_test_code(&relative, &chain, sources.content(&path)) {
+                    continue;
+                }
+            }
+            kept.push(item);
+        }
+        kept
+    }
+
+    /// Sorts references by file and position and labels each with its file and
+    /// enclosing items, found through the file's document symbols.
+    async fn group_references(
+        &self,
+        mut locations: Vec<lsp_types::Location>,
+    ) -> Vec<GroupedReference> {
+        locations.sort_by(|a, b| {
+            (self.paths.label_uri(&a.uri), a.range.start)
+                .cmp(&(self.paths.label_uri(&b.uri), b.range.start))
+        });
+
+        let mut sources = SourceFiles::new(&self.paths);
+        let mut file_symbols: HashMap<Url, Vec<DocumentSymbol>> = HashMap::new();
+        let mut references = Vec::with_capacity(locations.len());
+        for location in locations {
+            let file_path = self.paths.label_uri(&location.uri);
+            let Ok(path) = location.uri.to_file_path() else {
+                references.push(GroupedReference {
+                    location,
+                    file_path,
+                    item: TOP_LEVEL_GROUP.to_string(),
+                    in_tests: false,
+                });
+                continue;
+            };
+            if !file_symbols.contains_key(&location.uri) {
+                // Symbols are best effort; references are still listed without them
+                let symbols = self.nested_document_symbols(&path).await;
+                file_symbols.insert(location.uri.clone(), symbols.unwrap_or_default());
+            }
+
+            let chain = item_chain(&file_symbols[&location.uri], location.range.start);
+            let item = if chain.is_empty() {
+                TOP_LEVEL_GROUP.to_string()
+            } else {
+                chain
+                    .iter()
+                    .map(|symbol| item_label(symbol))
+                    .collect::<Vec<_>>()
+                    .join(" > ")
+            };
+            let relative = path.strip_prefix(&self.workspace_root).unwrap_or(&path);
+            let in_tests = is_test_code(relative, &chain, sources.content(&path));
+            references.push(GroupedReference {
+                location,
+                file_path,
+                item,
+                in_tests,
+            });
+        }
+        references
+    }
+
+    /// Reads the source context of LSP locations in the requested context mode.
+    ///
+    /// The `enclosing` mode looks up the innermost item around each location in its
+    /// file's document symbols; locations in files without symbols get no item.
+    async fn locations_in_context(
+        &self,
+        locations: &[lsp_types::Location],
+        context: LocationContext,
+    ) -> Vec<LocationWithContext> {
+        let context_lines = context_lines(context);
+        let mut sources = SourceFiles::new(&self.paths);
+        let mut file_symbols: HashMap<Url, Vec<DocumentSymbol>> = HashMap::new();
+        let mut result = Vec::with_capacity(locations.len());
+        for loc in locations {
+            let mut location = sources.location(&loc.uri, loc.range.start, context_lines);
+            match context {
+                LocationContext::Lines(_) => {}
+                LocationContext::Mode(ContextMode::None) => location.context.clear(),
+                LocationContext::Mode(ContextMode::Enclosing) => {
+                    let Ok(path) = loc.uri.to_file_path() else {
+                        result.push(location);
+                        continue;
+                    };
+                    if !file_symbols.contains_key(&loc.uri) {
+                        let symbols = self.nested_document_symbols(&path).await;
+                        file_symbols.insert(loc.uri.clone(), symbols.unwrap_or_default());
+                    }
+                    location.enclosing = innermost_item(&file_symbols[&loc.uri], loc.range.start)
+                        .map(|symbol| enclosing_item(sources.content(&path), symbol));
+                }
+            }
+            result.push(location);
+        }
+        result
+    }
+
+    /// Opens a document and returns its hierarchical symbols (empty for flat responses).
+    async fn nested_document_symbols(&self, path: &Path) -> Result<Vec<DocumentSymbol>, McpError> {
+        self.lsp_client.did_open(path).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to open document: {e}"),
+                None,
+            )
+        })?;
+        let symbols = self.lsp_client.document_symbols(path).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("document_symbols failed: {e}"),
+                None,
+            )
+        })?;
+        Ok(match symbols {
+            DocumentSymbolResponse::Nested(symbols) => symbols,
+            DocumentSymbolResponse::Flat(_) => Vec::new(),
+        })
+    }
+}
+
+/// Maximum number of nested modules followed when walking up to a crate root.
+const MAX_MODULE_NESTING: usize = 64;
+
+/// A symbol reached while resolving a Rust path.
+#[derive(Debug, Clone)]
+struct PathTarget {
+    /// Location of the symbol's name.
+    location: lsp_types::Location,
+    /// Kind of the symbol.
+    kind: SymbolKind,
+    /// Path below the crate root, e.g. `["shapes", "Square", "area"]`.
+    segments: Vec<String>,
+    /// Name of the enclosing impl block, e.g. `impl Area for Square`.
+    impl_block: Option<String>,
+}
+
+impl PathTarget {
+    /// Returns whether the symbol is declared in an inherent impl block.
+    fn is_inherent_method(&self) -> bool {
+        self.impl_block
+            .as_deref()
+            .and_then(impl_trait_and_self_type)
+            .is_some_and(|(as_trait, _)| as_trait.is_none())
+    }
+}
+
+/// A file position resolved from tool parameters.
+#[derive(Debug)]
+struct ResolvedPosition {
+    /// Path to the file.
+    file_path: PathBuf,
+    /// Line number (1-indexed).
+    line: u32,
+    /// Column number (1-indexed).
+    column: u32,
+    /// How the column was picked when the caller did not pin it down, shown before the output.
+    note: Option<String>,
+}
+
+/// Prepends the note of a resolved position to a tool's text output.
+fn with_note(note: Option<&str>, text: impl Into<String>) -> String {
+    match note {
+        Some(note) => format!("Note: {note}\n\n{}", text.into()),
+        None => text.into(),
+    }
+}
+
+/// Returns the identifiers on a source line as byte offsets and text.
+fn line_identifiers(text: &str) -> Vec<(usize, &str)> {
+    let mut identifiers = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if !(c.is_alphanumeric() || c == '_') {
+            continue;
+        }
+        let mut end = start + c.len_utf8();
+        while let Some(&(index, c)) = chars.peek() {
+            if !(c.is_alphanumeric() || c == '_') {
+                break;
+            }
+            end = index + c.len_utf8();
+            chars.next();
+        }
+        // Number literals are not identifiers
+        if !c.is_numeric() {
+            identifiers.push((start, &text[start..end]));
+        }
+    }
+    identifiers
+}
+
+/// Anchors a 1-indexed column on the source line `text` to an identifier.
+///
+/// With `symbol_text` the column moves to that token: the 1-based `occurrence` if
+/// given, else the occurrence nearest `column`, else the first. Without it, a column
+/// on whitespace or punctuation snaps to the nearest identifier. Returns the column
+/// and a note naming the pick when the caller left a choice open.
+fn anchor_column(
+    text: &str,
+    line: u32,
+    column: u32,
+    symbol_text: Option<&str>,
+    occurrence: Option<u32>,
+) -> Result<(u32, Option<String>), String> {
+    let identifiers = line_identifiers(text);
+    let at = utf16_offset_to_byte(text, column.saturating_sub(1));
+    let to_column = |byte| byte_to_utf16_offset(text, byte) + 1;
+
+    let Some(symbol_text) = symbol_text else {
+        let on_identifier = text[at..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_');
+        if column == 0 || on_identifier {
+            return Ok((column, None));
+        }
+        // Prefer the identifier to the right on ties, where a cursor usually sits before it
+        let Some(&(start, name)) = identifiers.iter().min_by_key(|(start, name)| {
+            let end = start + name.len();
+            let distance = if at < *start {
+                start - at
+            } else {
+                (at + 1).saturating_sub(end)
+            };
+            (distance, *start < at)
+        }) else {
+            return Ok((column, None));
+        };
+        let snapped = to_column(start);
+        let note = format!(
+            "column {column} on line {line} is not on an identifier; snapped to `{name}` at column {snapped}"
+        );
+        return Ok((snapped, Some(note)));
+    };
+
+    let mut occurrences: Vec<usize> = identifiers
+        .iter()
+        .filter(|(_, name)| *name == symbol_text)
+        .map(|(start, _)| *start)
+        .collect();
+    // Operators and other non-identifier tokens are matched as plain text
+    let is_identifier = symbol_text.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if !is_identifier {
+        occurrences = text
+            .match_indices(symbol_text)
+            .map(|(start, _)| start)
+            .collect();
+    }
+    if occurrences.is_empty() {
+        let mut names: Vec<&str> = Vec::new();
+        for (_, name) in &identifiers {
+            if !names.contains(name) {
+                names.push(name);
+            }
+        }
+        let names = if names.is_empty() {
+            "(none)".to_string()
+        } else {
+            names.join(", ")
+        };
+        return Err(format!(
+            "`{symbol_text}` not found on line {line}; identifiers on that line: {names}"
+        ));
+    }
+
+    let count = occurrences.len();
+    match occurrence {
+        Some(occurrence) => {
+            let start = occurrence
+                .checked_sub(1)
+                .and_then(|index| occurrences.get(index as usize))
+                .ok_or_else(|| {
+                    format!(
+                        "occurrence {occurrence} of `{symbol_text}` does not exist; line {line} has {count}"
+                    )
+                })?;
+            Ok((to_column(*start), None))
+        }
+        None if count == 1 => Ok((to_column(occurrences[0]), None)),
+        None => {
+            let (start, picked) = if column == 0 {
+                (occurrences[0], "the first".to_string())
+            } else {
+                let nearest = occurrences
+                    .iter()
+                    .copied()
+                    .min_by_key(|start| start.abs_diff(at))
+                    .unwrap_or(occurrences[0]);
+                (nearest, format!("the one nearest column {column}"))
+            };
+            let anchored = to_column(start);
+            let note = format!(
+                "`{symbol_text}` occurs {count} times on line {line}; picked {picked} at column {anchored} (pass occurrence to choose another)"
+            );
+            Ok((anchored, Some(note)))
+        }
+    }
+}
+
+/// Returns the path targets of the symbols named `name` among `symbols`, below `parent`.
+fn member_targets<'a>(
+    uri: &'a Url,
+    symbols: &'a [DocumentSymbol],
+    parent: &'a PathTarget,
+    name: &'a str,
+    impl_block: Option<&'a str>,
+) -> impl Iterator<Item = PathTarget> + 'a {
+    symbols
+        .iter()
+        .filter(move |symbol| symbol.name == name)
+        .map(move |symbol| PathTarget {
+            location: lsp_types::Location {
+                uri: uri.clone(),
+                range: symbol.selection_range,
+            },
+            kind: symbol.kind,
+            segments: parent
+                .segments
+                .iter()
+                .cloned()
+                .chain(std::iter::once(symbol.name.clone()))
+                .collect(),
+            impl_block: impl_block.map(ToString::to_string),
+        })
+}
+
+/// Formats a path target as `crate::path (impl block) [kind] - file:line`.
+fn format_path_target(paths: &PathLabels, target: &PathTarget) -> String {
+    let file_path = paths.label_uri(&target.location.uri);
+    let (line, _) = from_lsp_position(target.location.range.start);
+    let impl_block = target
+        .impl_block
+        .as_ref()
+        .map_or_else(String::new, |block| format!(" ({block})"));
+    format!(
+        "  crate::{}{impl_block} [{}] - {file_path}:{line}",
+        target.segments.join("::"),
+        symbol_kind_to_string(target.kind)
+    )
+}
+
+/// Direction to walk a type hierarchy in.
+#[derive(Debug, Clone, Copy)]
+enum HierarchyDirection {
+    /// Towards supertypes (supertraits, base classes).
+    Supertypes,
+    /// Towards subtypes (implementors, derived classes).
+    Subtypes,
+}
+
+// Helper functions for formatting LSP responses
+// Note: These are called by the #[tool_router] macro-generated code,
+// but the compiler's dead code analysis doesn't see through macros.
+
+/// Reads context lines around a specific line in a file.
+///
+/// Returns a formatted string with line numbers and a marker for the target line.
+#[allow(dead_code)]
+fn read_context_lines(path: &Path, line: u32, context: usize) -> Result<String, std::io::Error> {
+    let file_content = std::fs::read_to_string(path)?;
+    let lines: Vec<_> = file_content.lines().collect();
+    let line_idx = line.saturating_sub(1) as usize; // Convert to 0-indexed
+
+    // A line at or past the end of the file (e.g. an insertion at EOF) has no text
+    let end = (line_idx + context + 1).min(lines.len());
+    let start = line_idx.saturating_sub(context).min(end);
+
+    let mut result = String::new();
+    for (idx, line_text) in lines[start..end].iter().enumerate() {
+        let line_num = start + idx + 1;
+        let marker = if line_num == (line_idx + 1) { ">" } else { " " };
+        let _ = writeln!(result, "{marker} {line_num:4} | {line_text}");
+    }
+    Ok(result)
+}
+
+/// Truncates `text` to at most `max_chars` characters.
+///
+/// Text that fits is returned unchanged. Otherwise the text is cut at the last line
+/// break within the budget (or at the budget itself for a single long line) and a
+/// note with the shown and total character counts is appended.
+#[allow(dead_code)]
+fn truncate_output(text: &str, max_chars: usize) -> String {
+    let Some((cut, _)) = text.char_indices().nth(max_chars) else {
+        return text.to_string();
+    };
+
+    let cut = match text[..cut].rfind('\n') {
+        Some(newline) if newline > 0 => newline + 1,
+        _ => cut,
+    };
+
+    let shown = &text[..cut];
+    format!(
+        "{shown}\n... truncated: showing {} of {} characters\n",
+        shown.chars().count(),
+        text.chars().count()
+    )
+}
+
+/// Formats a macro expansion as a Rust code block of at most `max_chars` characters.
+fn format_macro_expansion(name: &str, expansion: &str, max_chars: usize) -> String {
+    let code = truncate_output(expansion, max_chars);
+    let newline = if code.ends_with('\n') { "" } else { "\n" };
+    format!("Expansion of {name}:\n```rust\n{code}{newline}```")
+}
+
+/// Number of source lines shown before and after each `ssr_preview` match.
+const SSR_CONTEXT_LINES: usize = 2;
+
+/// Formats an SSR preview as its matches followed by a diff code block, together at
+/// most `max_chars` characters.
+///
+/// The diff's code block stays closed when the output is truncated inside it.
+fn format_ssr_preview(matches: &str, diff: &str, max_chars: usize) -> String {
+    const DIFF_FENCE: &str = "\nDiff:\n```diff\n";
+    let mut text = truncate_output(&format!("{matches}{DIFF_FENCE}{diff}"), max_chars);
+    if text.contains(DIFF_FENCE) {
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push_str("```\n");
+    }
+    text
+}
+
+/// Source files read while building structured output, each read at most once.
+struct SourceFiles<'a> {
+    /// Short path forms for the locations built.
+    paths: &'a PathLabels,
+    /// Contents of the files read so far, `None` for unreadable files.
+    files: HashMap<PathBuf, Option<String>>,
+}
+
+impl<'a> SourceFiles<'a> {
+    /// Creates an empty cache rendering paths with `paths`.
+    fn new(paths: &'a PathLabels) -> Self {
+        Self {
+            paths,
+            files: HashMap::new(),
+        }
+    }
+
+    /// Returns the location of `position` in `uri` with `context_lines` lines around it.
+    ///
+    /// Locations in files that cannot be read keep an empty source line.
+    fn location(
+        &mut self,
+        uri: &Url,
+        position: Position,
+        context_lines: usize,
+    ) -> LocationWithContext {
+        let (line, column) = from_lsp_position(position);
+        let Ok(path) = uri.to_file_path() else {
+            return LocationWithContext {
+                file_path: self.paths.label_uri(uri),
+                line,
+                column,
+                context: String::new(),
+                context_before: None,
+                context_after: None,
+                enclosing: None,
+            };
+        };
+
+        let file_path = self.paths.label(&path);
+        let content = self.content(&path);
+        location_in_content(file_path, content, line, column, context_lines)
+    }
+
+    /// Records the already read content of the file at `path`.
+    fn insert(&mut self, path: PathBuf, content: String) {
+        self.files.insert(path, Some(content));
+    }
+
+    /// Returns the content of the file at `path`, empty if it cannot be read.
+    fn content(&mut self, path: &Path) -> &str {
+        self.files
+            .entry(path.to_path_buf())
+            .or_insert_with(|| std::fs::read_to_string(path).ok())
+            .as_deref()
+            .unwrap_or_default()
+    }
+}
+
+/// Builds a location at a 1-indexed `line` and `column` of `content`, with up to
+/// `context_lines` lines before and after it.
+fn location_in_content(
+    file_path: String,
+    content: &str,
+    line: u32,
+    column: u32,
+    context_lines: usize,
+) -> LocationWithContext {
+    let lines: Vec<_> = content.lines().collect();
+    let index = (line.saturating_sub(1) as usize).min(lines.len());
+    let start = index.saturating_sub(context_lines);
+    let end = (index + context_lines + 1).min(lines.len());
+    let to_strings = |lines: &[&str]| lines.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+    LocationWithContext {
+        file_path,
+        line,
+        column,
+        context: lines.get(index).copied().unwrap_or_default().to_string(),
+        context_before: (context_lines > 0).then(|| to_strings(&lines[start..index])),
+        context_after: (context_lines > 0).then(|| to_strings(&lines[(index + 1).min(end)..end])),
+        enclosing: None,
+    }
+}
+
+/// Maximum number of lines shown of an enclosing item's signature.
+const MAX_SIGNATURE_LINES: usize = 8;
+
+/// Maximum number of context lines shown before and after a location.
+///
+/// Pages keep at least one location regardless of the output budget, so this bounds
+/// the size of a single location.
+const MAX_CONTEXT_LINES: u32 = 50;
+
+/// Returns the number of source lines to show before and after each location.
+fn context_lines(context: LocationContext) -> usize {
+    match context {
+        LocationContext::Lines(lines) => lines.min(MAX_CONTEXT_LINES) as usize,
+        LocationContext::Mode(_) => 0,
+    }
+}
+
+/// Returns the items among `symbols` whose range contains `position`, outermost first.
+///
+/// Fields and enum variants are skipped, so a location in a field's type ends at the
+/// struct around it.
+fn item_chain(symbols: &[DocumentSymbol], position: Position) -> Vec<&DocumentSymbol> {
+    let mut chain = Vec::new();
+    let mut level = symbols;
+    while let Some(symbol) = level.iter().find(|symbol| {
+        symbol.range.start <= position
+            && position <= symbol.range.end
+            && !matches!(symbol.kind, SymbolKind::FIELD | SymbolKind::ENUM_MEMBER)
+    }) {
+        chain.push(symbol);
+        level = symbol.children.as_deref().unwrap_or_default();
+    }
+    chain
+}
+
+/// Returns the innermost item among `symbols` whose range contains `position`.
+fn innermost_item(symbols: &[DocumentSymbol], position: Position) -> Option<&DocumentSymbol> {
+    item_chain(symbols, position).pop()
+}
+
+/// Returns an item as written in Rust, e.g. `fn handle_request` or `impl Display for Point`.
+fn item_label(symbol: &DocumentSymbol) -> String {
+    let keyword = match symbol.kind {
+        SymbolKind::FUNCTION | SymbolKind::METHOD => "fn",
+        SymbolKind::STRUCT => "struct",
+        SymbolKind::ENUM => "enum",
+        SymbolKind::INTERFACE => "trait",
+        SymbolKind::MODULE => "mod",
+        SymbolKind::CONSTANT => "const",
+        SymbolKind::TYPE_PARAMETER => "type",
+        // Impl blocks are named `impl Trait for Type` already
+        _ => return symbol.name.clone(),
+    };
+    format!("{keyword} {}", symbol.name)
+}
+
+/// Describes `symbol` as the item enclosing a location, reading its signature from
+/// `content`.
+///
+/// The signature runs from the line of the item's name to the line opening its body
+/// or ending the declaration, at most [`MAX_SIGNATURE_LINES`] lines.
+fn enclosing_item(content: &str, symbol: &DocumentSymbol) -> EnclosingItem {
+    let first = symbol.selection_range.start.line as usize;
+    let last = symbol.range.end.line as usize;
+    let mut signature = Vec::new();
+    for line in content
+        .lines()
+        .skip(first)
+        .take((last + 1).saturating_sub(first).min(MAX_SIGNATURE_LINES))
+    {
+        let line = line.trim_end();
+        signature.push(line.to_string());
+        if line.contains('{') || line.ends_with(';') {
+            break;
+        }
+    }
+
+    EnclosingItem {
+        name: item_label(symbol),
+        start_line: symbol.range.start.line + 1,
+        end_line: symbol.range.end.line + 1,
+        signature_line: symbol.selection_range.start.line + 1,
+        signature,
+    }
+}
+
+/// Formats a location as `path:line:column` followed by its numbered source lines.
+///
+/// A location with an enclosing item shows the item's name, line span and signature
+/// above the location's line; one without source lines shows only the header.
+fn format_location(location: &LocationWithContext) -> String {
+    let before = location.context_before.as_deref().unwrap_or_default();
+    let after = location.context_after.as_deref().unwrap_or_default();
+    let first = (location.line as usize).saturating_sub(before.len());
+
+    let mut result = format!(
+        "{}:{}:{}\n",
+        location.file_path, location.line, location.column
+    );
+    if let Some(item) = &location.enclosing {
+        let _ = writeln!(
+            result,
+            "inside `{}` (lines {}\u{2013}{})",
+            item.name, item.start_line, item.end_line
+        );
+        let mut shown = false;
+        for (offset, line_text) in item.signature.iter().enumerate() {
+            let line_num = item.signature_line as usize + offset;
+            let marker = if line_num == location.line as usize {
+                shown = true;
+                ">"
+            } else {
+                " "
+            };
+            let _ = writeln!(result, "{marker} {line_num:4} | {line_text}");
+        }
+        if !shown {
+            let signature_end = item.signature_line as usize + item.signature.len();
+            if (location.line as usize) > signature_end {
+                result.push_str("         ...\n");
+            }
+            let _ = writeln!(result, "> {:4} | {}", location.line, location.context);
+        }
+        return result;
+    }
+    if location.context.is_empty() && before.is_empty() && after.is_empty() {
+        return result;
+    }
+
+    let lines = before
+        .iter()
+        .chain(std::iter::once(&location.context))
+        .chain(after);
+    for (offset, line_text) in lines.enumerate() {
+        let line_num = first + offset;
+        let marker = if line_num == location.line as usize {
+            ">"
+        } else {
+            " "
+        };
+        let _ = writeln!(result, "{marker} {line_num:4} | {line_text}");
+    }
+    result
+}
+
+/// Searches for a symbol by exact name in document symbols.
+///
+/// Returns (line, column) if found, None otherwise.
+fn find_symbol_in_document(
+    doc_symbols: &DocumentSymbolResponse,
+    symbol_name: &str,
+) -> Option<(u32, u32)> {
+    match doc_symbols {
+        DocumentSymbolResponse::Flat(symbols) => {
+            // Search flat symbol list
+            symbols
+                .iter()
+                .find(|sym| sym.name == symbol_name)
+                .map(|sym| from_lsp_position(sym.location.range.start))
+        }
+        DocumentSymbolResponse::Nested(symbols) => {
+            // Search nested symbol hierarchy recursively
+            find_in_nested_symbols(symbols, symbol_name)
+        }
+    }
+}
+
+/// Recursively searches nested `DocumentSymbol` tree for exact match.
+fn find_in_nested_symbols(symbols: &[DocumentSymbol], symbol_name: &str) -> Option<(u32, u32)> {
+    for symbol in symbols {
+        // Check current symbol
+        if symbol.name == symbol_name {
+            return Some(from_lsp_position(symbol.selection_range.start));
+        }
+
+        // Recursively check children
+        if let Some(children) = &symbol.children
+            && let Some(result) = find_in_nested_symbols(children, symbol_name)
+        {
+            return Some(result);
+        }
+    }
+    None
+}
+
+/// Formats multiple locations with context.
+fn format_locations(locations: &[LocationWithContext]) -> String {
+    if locations.is_empty() {
+        return "No results found.".to_string();
+    }
+
+    locations
+        .iter()
+        .map(format_location)
+        .collect::<Vec<_>>()
+        .join("\n\n---\n\n")
+}
+
+/// Number of results on a page when the request sets no `limit`.
+const DEFAULT_PAGE_LIMIT: u32 = 100;
+
+/// Maximum number of files listed in the summary of a partial page.
+const SUMMARY_FILES: usize = 10;
+
+/// Returns the index of the first result of the requested page.
+///
+/// A cursor from a previous page takes precedence over an explicit offset.
+fn page_offset(page: &PageParams) -> Result<u32, McpError> {
+    let Some(cursor) = &page.cursor else {
+        return Ok(page.offset);
+    };
+    cursor
+        .strip_prefix("offset:")
+        .and_then(|offset| offset.parse().ok())
+        .ok_or_else(|| {
+            McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!("invalid cursor '{cursor}'; pass the nextCursor of a previous page"),
+                None,
+            )
+        })
+}
+
+/// Cuts the page requested by `page` out of `items`.
+///
+/// The page info counts the results in each file across all items, grouping them by
+/// `file_of`.
+fn paginate<T>(
+    items: Vec<T>,
+    page: &PageParams,
+    default_limit: u32,
+    file_of: impl Fn(&T) -> String,
+) -> Result<(Vec<T>, PageInfo), McpError> {
+    let offset = page_offset(page)?;
+    let limit = page.limit.unwrap_or(default_limit);
+    // An empty page would return a cursor to itself
+    if limit == 0 {
+        return Err(McpError::new(
+            ErrorCode::INVALID_PARAMS,
+            "limit must be at least 1".to_string(),
+            None,
+        ));
+    }
+
+    let mut counts: HashMap<String, u32> = HashMap::new();
+    for item in &items {
+        *counts.entry(file_of(item)).or_default() += 1;
+    }
+    let mut per_file: Vec<_> = counts
+        .into_iter()
+        .map(|(file_path, count)| FileCount { file_path, count })
+        .collect();
+    per_file.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.file_path.cmp(&b.file_path))
+    });
+
+    let total = u32::try_from(items.len()).unwrap_or(u32::MAX);
+    let items: Vec<_> = items
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit as usize)
+        .collect();
+    let mut info = PageInfo {
+        total,
+        offset,
+        returned: 0,
+        per_file,
+        next_cursor: None,
+    };
+    set_page_returned(&mut info, items.len());
+    Ok((items, info))
+}
+
+/// Records how many results a page shows and the cursor of the page after it.
+fn set_page_returned(info: &mut PageInfo, returned: usize) {
+    info.returned = u32::try_from(returned).unwrap_or(u32::MAX);
+    let next = info.offset.saturating_add(info.returned);
+    info.next_cursor = (next < info.total).then(|| format!("offset:{next}"));
+}
+
+/// Renders the items of a page within `max_chars` characters, keeping at least one.
+///
+/// Items that do not fit are dropped from `items` and left for the next page.
+fn render_page<T>(
+    items: &mut Vec<T>,
+    info: &mut PageInfo,
+    max_chars: u32,
+    render: impl Fn(&T) -> String,
+) -> Vec<String> {
+    let mut used = 0;
+    let mut rendered = Vec::new();
+    for item in items.iter() {
+        let text = render(item);
+        used += text.chars().count();
+        if used > max_chars as usize && !rendered.is_empty() {
+            break;
+        }
+        rendered.push(text);
+    }
+
+    items.truncate(rendered.len());
+    set_page_returned(info, rendered.len());
+    rendered
+}
+
+/// Joins the rendered items of a page.
+///
+/// A page holding only part of the results starts with a summary of all of them: the
+/// total, the counts per file and the cursor of the next page.
+fn format_page(info: &PageInfo, rendered: &[String], separator: &str) -> String {
+    let body = rendered.join(separator);
+    if info.offset == 0 && info.returned == info.total {
+        return body;
+    }
+
+    let mut summary = if info.returned == 0 {
+        format!(
+            "No results at offset {}; {} result(s) in {} file(s):\n",
+            info.offset,
+            info.total,
+            info.per_file.len()
+        )
+    } else {
+        format!(
+            "Showing results {}-{} of {} in {} file(s):\n",
+            info.offset + 1,
+            info.offset + info.returned,
+            info.total,
+            info.per_file.len()
+        )
+    };
+    for file in info.per_file.iter().take(SUMMARY_FILES) {
+        let _ = writeln!(summary, "  {}: {}", file.file_path, file.count);
+    }
+    if info.per_file.len() > SUMMARY_FILES {
+        let _ = writeln!(
+            summary,
+            "  ... and {} more file(s)",
+            info.per_file.len() - SUMMARY_FILES
+        );
+    }
+    if let Some(cursor) = &info.next_cursor {
+        let _ = writeln!(summary, "Next page: cursor \"{cursor}\"");
+    }
+
+    format!("{summary}\n{body}")
+}
+
+/// Returns whether `path` matches a glob `pattern`.
+///
+/// `*` and `?` match within one path segment and `**` matches any number of segments.
+/// A pattern without `/` matches the file name alone.
+fn glob_matches(pattern: &str, path: &str) -> bool {
+    let segments = |text: &str| -> Vec<String> {
+        text.split(['/', '\\'])
+            .filter(|segment| !segment.is_empty())
+            .map(ToString::to_string)
+            .collect()
+    };
+    let path = segments(path);
+    if !pattern.contains('/') {
+        return path
+            .last()
+            .is_some_and(|name| glob_segment_matches(pattern, name));
+    }
+    glob_segments_match(&segments(pattern), &path)
+}
+
+/// Matches path segments against glob segments, where `**` spans any number of them.
+fn glob_segments_match(pattern: &[String], path: &[String]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..=path.len()).any(|skip| glob_segments_match(rest, &path[skip..]))
+        }
+        Some((first, rest)) => path.split_first().is_some_and(|(segment, path)| {
+            glob_segment_matches(first, segment) && glob_segments_match(rest, path)
+        }),
+    }
+}
+
+/// Matches one path segment against a glob segment with `*` and `?` wildcards.
+fn glob_segment_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text index it was tried at
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((after_star, tried)) => {
+                    p = after_star;
+                    t = tried + 1;
+                    star = Some((after_star, tried + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Group of the references outside any item.
+const TOP_LEVEL_GROUP: &str = "(top level)";
+
+/// A reference labeled with the file and enclosing items it is grouped under.
+struct GroupedReference {
+    /// The reference.
+    location: lsp_types::Location,
+    /// Path of the file, as shown in the summary.
+    file_path: String,
+    /// Enclosing items from the outermost, e.g. `impl Server > fn handle_request`.
+    item: String,
+    /// Whether the reference is in test code.
+    in_tests: bool,
+}
+
+/// Returns whether a position inside the items of `chain` is test code.
+///
+/// Files under a `tests` directory or named `tests.rs`, `mod tests`, and modules or
+/// functions with a test attribute such as `#[cfg(test)]`, `#[test]` or
+/// `#[tokio::test]` count as tests.
+fn is_test_code(path: &Path, chain: &[&DocumentSymbol], content: &str) -> bool {
+    if path.file_stem().is_some_and(|stem| stem == "tests")
+        || path
+            .components()
+            .any(|component| component.as_os_str() == "tests")
+    {
+        return true;
+    }
+    // The symbol range starts at the item's attributes
+    let has_test_attribute = |symbol: &DocumentSymbol| {
+        let start = symbol.range.start.line as usize;
+        let end = symbol.selection_range.start.line as usize;
+        content
+            .lines()
+            .skip(start)
+            .take(end.saturating_sub(start))
+            .map(str::trim_start)
+            .any(is_test_attribute)
+    };
+    chain.iter().any(|symbol| match symbol.kind {
+        SymbolKind::MODULE => {
+            symbol.name == "tests" || symbol.name == "test" || has_test_attribute(symbol)
+        }
+        SymbolKind::FUNCTION | SymbolKind::METHOD => has_test_attribute(symbol),
+        _ => false,
+    })
+}
+
+/// Returns whether a source line starts with a test attribute: `#[test]`, a path
+/// ending in `::test` such as `#[tokio::test]`, or a `#[cfg(..)]` that requires `test`.
+fn is_test_attribute(line: &str) -> bool {
+    let Some(attribute) = line.strip_prefix("#[") else {
+        return false;
+    };
+    let attribute = attribute
+        .find(']')
+        .map_or(attribute, |end| &attribute[..end]);
+    let (name, arguments) = match attribute.split_once('(') {
+        Some((name, arguments)) => (name.trim(), arguments.rsplit_once(')').map(|(a, _)| a)),
+        None => (attribute.trim(), None),
+    };
+    match (name, arguments) {
+        ("cfg", Some(predicate)) => cfg_requires_test(predicate),
+        _ => name == "test" || name.ends_with("::test"),
+    }
+}
+
+/// Returns whether a `cfg` predicate can only hold in test builds.
+fn cfg_requires_test(predicate: &str) -> bool {
+    let predicate = predicate.trim();
+    let arguments = |prefix: &str| {
+        predicate
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.trim_start().strip_prefix('('))
+            .and_then(|rest| rest.strip_suffix(')'))
+            .map(split_cfg_arguments)
+    };
+    if predicate == "test" {
+        true
+    } else if let Some(arguments) = arguments("all") {
+        arguments.into_iter().any(cfg_requires_test)
+    } else if let Some(arguments) = arguments("any") {
+        !arguments.is_empty() && arguments.into_iter().all(cfg_requires_test)
+    } else {
+        false
+    }
+}
+
+/// Splits the arguments of a `cfg` predicate such as `all(..)` at top-level commas.
+fn split_cfg_arguments(arguments: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut in_string, mut start) = (0, false, 0);
+    for (index, c) in arguments.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => depth -= 1,
+            ',' if !in_string && depth == 0 => {
+                parts.push(&arguments[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&arguments[start..]);
+    parts.retain(|part| !part.trim().is_empty());
+    parts
+}
+
+/// Returns whether a reference is in the file and item groups being expanded.
+///
+/// A file matches by its full path or a path suffix; an item matches by its full
+/// label, its last part, or as an outer item of the reference's item.
+fn in_reference_group(
+    reference: &GroupedReference,
+    group_file: Option<&str>,
+    group_item: Option<&str>,
+) -> bool {
+    let file_matches = group_file.is_none_or(|file| {
+        reference.file_path == file || Path::new(&reference.file_path).ends_with(file)
+    });
+    let item_matches = group_item.is_none_or(|item| {
+        reference.item == item
+            || reference.item.ends_with(&format!(" {item}"))
+            || reference.item.starts_with(&format!("{item} > "))
+    });
+    file_matches && item_matches
+}
+
+/// Counts references by file and enclosing item, in the order of `references`.
+fn summarize_references(references: &[GroupedReference]) -> ReferenceSummary {
+    let mut summary = ReferenceSummary::default();
+    for reference in references {
+        summary.total += 1;
+        if reference.in_tests {
+            summary.in_tests += 1;
+        }
+
+        let file = match summary.files.last_mut() {
+            Some(file) if file.file_path == reference.file_path => file,
+            _ => {
+                summary.files.push(FileGroup {
+                    file_path: reference.file_path.clone(),
+                    count: 0,
+                    items: Vec::new(),
+                });
+                summary
+                    .files
+                    .last_mut()
+                    .expect("file group was just pushed")
+            }
+        };
+        file.count += 1;
+        match file
+            .items
+            .iter_mut()
+            .find(|group| group.item == reference.item)
+        {
+            Some(group) => group.count += 1,
+            None => file.items.push(ItemGroup {
+                item: reference.item.clone(),
+                count: 1,
+                in_tests: reference.in_tests,
+            }),
+        }
+    }
+    summary
+}
+
+/// Returns `count` followed by `noun`, pluralized with an `s` unless `count` is one.
+fn plural(count: u32, noun: &str) -> String {
+    if count == 1 {
+        format!("{count} {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
+
+/// Formats the headline of a reference summary, e.g. `47 references in 12 files; 9 in tests`.
+fn format_reference_header(summary: &ReferenceSummary) -> String {
+    let mut header = format!(
+        "{} in {}",
+        plural(summary.total, "reference"),
+        plural(
+            u32::try_from(summary.files.len()).unwrap_or(u32::MAX),
+            "file"
+        )
+    );
+    if summary.in_tests > 0 {
+        let _ = write!(header, "; {} in tests", summary.in_tests);
+    }
+    header
+}
+
+/// Formats a reference summary as its headline and the counts of each file and item.
+fn format_reference_summary(summary: &ReferenceSummary) -> String {
+    let mut result = format_reference_header(summary);
+    result.push('\n');
+    for file in &summary.files {
+        let _ = writeln!(result, "\n{} ({})", file.file_path, file.count);
+        for group in &file.items {
+            let test = if group.in_tests { " [test]" } else { "" };
+            let _ = writeln!(result, "  {} ({}){test}", group.item, group.count);
+        }
+    }
+    result
+}
+
+/// Formats a page of references under headings for their file and enclosing item.
+///
+/// `references` and `rendered` are the references of the page and their rendered
+/// locations; the headline and group counts cover all references in `summary`.
+fn format_grouped_references(
+    summary: &ReferenceSummary,
+    page: &PageInfo,
+    references: &[GroupedReference],
+    rendered: &[String],
+) -> String {
+    let mut result = format_reference_header(summary);
+    result.push('\n');
+    if page.offset > 0 || page.returned < page.total {
+        let _ = write!(
+            result,
+            "Showing references {}-{} of {}",
+            page.offset + 1,
+            page.offset + page.returned,
+            page.total
+        );
+        if let Some(cursor) = &page.next_cursor {
+            let _ = write!(result, "; next page: cursor \"{cursor}\"");
+        }
+        result.push('\n');
+    }
+
+    let mut current: Option<(&str, &str)> = None;
+    for (reference, text) in references.iter().zip(rendered) {
+        let file = summary
+            .files
+            .iter()
+            .find(|file| file.file_path == reference.file_path);
+        if current.is_none_or(|(file_path, _)| file_path != reference.file_path) {
+            let count = file.map_or(0, |file| file.count);
+            let _ = writeln!(result, "\n{} ({count})", reference.file_path);
+        }
+        if current == Some((&reference.file_path, &reference.item)) {
+            result.push('\n');
+        } else {
+            let count = file
+                .and_then(|file| file.items.iter().find(|group| group.item == reference.item))
+                .map_or(0, |group| group.count);
+            let test = if reference.in_tests { " [test]" } else { "" };
+            let _ = writeln!(result, "  {} ({count}){test}", reference.item);
+        }
+        current = Some((&reference.file_path, &reference.item));
+        for line in text.lines() {
+            let _ = writeln!(result, "    {line}");
+        }
+    }
+    result
+}
+
+/// Converts `GotoDefinitionResponse` to a list of locations.
+#[allow(dead_code)]
+fn goto_response_to_locations(response: GotoDefinitionResponse) -> Vec<lsp_types::Location> {
+    match response {
+        GotoDefinitionResponse::Scalar(loc) => vec![loc],
+        GotoDefinitionResponse::Array(locs) => locs,
+        // Point at the symbol name rather than the start of the enclosing item
+        GotoDefinitionResponse::Link(links) => links
+            .into_iter()
+            .map(|link| lsp_types::Location {
+                uri: link.target_uri,
+                range: link.target_selection_range,
+            })
+            .collect(),
+    }
+}
+
+/// Extracts markdown text from `MarkupContent` or string.
+#[allow(dead_code)]
+fn extract_hover_text(content: lsp_types::HoverContents) -> String {
+    match content {
+        lsp_types::HoverContents::Scalar(marked_string) => match marked_string {
+            lsp_types::MarkedString::String(s) => s,
+            lsp_types::MarkedString::LanguageString(ls) => {
+                format!("```{}\n{}\n```", ls.language, ls.value)
+            }
+        },
+        lsp_types::HoverContents::Array(marked_strings) => marked_strings
+            .into_iter()
+            .map(|ms| match ms {
+                lsp_types::MarkedString::String(s) => s,
+                lsp_types::MarkedString::LanguageString(ls) => {
+                    format!("```{}\n{}\n```", ls.language, ls.value)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        lsp_types::HoverContents::Markup(markup) => markup.value,
+    }
+}
+
+/// Splits hover markdown into the declaration's signature and its documentation.
+///
+/// rust-analyzer renders hovers as code blocks (the container, then the declaration)
+/// followed by a `---` rule and the docs. The last code block before the rule is the
+/// signature; hovers without code blocks are treated as documentation only.
+fn parse_hover_text(text: &str) -> HoverResult {
+    let (head, docs) = match text.split_once("\n---\n") {
+        Some((head, docs)) => (head, Some(docs)),
+        None => (text, None),
+    };
+
+    let mut signature = None;
+    let mut block: Option<Vec<&str>> = None;
+    for line in head.lines() {
+        match block.as_mut() {
+            Some(lines) if line.trim_start().starts_with("```") => {
+                signature = Some(lines.join("\n"));
+                block = None;
+            }
+            Some(lines) => lines.push(line),
+            None if line.trim_start().starts_with("```") => block = Some(Vec::new()),
+            None => {}
+        }
+    }
+
+    let documentation = match (&signature, docs) {
+        (None, _) => Some(text),
+        (Some(_), docs) => docs,
+    }
+    .map(str::trim)
+    .filter(|docs| !docs.is_empty())
+    .map(ToString::to_string);
+
+    HoverResult {
+        signature: signature.filter(|signature| !signature.trim().is_empty()),
+        documentation,
+    }
+}
+
+/// Flattens nested document symbols of the file at `path` for structured output.
+///
+/// Each symbol's container is the name of its parent symbol.
+fn document_symbol_infos(
+    file_path: &str,
+    content: &str,
+    symbols: &[DocumentSymbol],
+    container: Option<&str>,
+    infos: &mut Vec<SymbolInfo>,
+) {
+    for symbol in symbols {
+        let (line, column) = from_lsp_position(symbol.selection_range.start);
+        infos.push(SymbolInfo {
+            name: symbol.name.clone(),
+            kind: symbol_kind_to_string(symbol.kind).to_string(),
+            location: location_in_content(file_path.to_string(), content, line, column, 0),
+            container: container.map(ToString::to_string),
+        });
+        if let Some(children) = &symbol.children {
+            document_symbol_infos(file_path, content, children, Some(&symbol.name), infos);
+        }
+    }
+}
+
+/// Converts flat symbol information for structured output.
+fn symbol_information_infos(
+    paths: &PathLabels,
+    symbols: &[lsp_types::SymbolInformation],
+) -> Vec<SymbolInfo> {
+    let mut sources = SourceFiles::new(paths);
+    symbols
+        .iter()
+        .map(|symbol| SymbolInfo {
+            name: symbol.name.clone(),
+            kind: symbol_kind_to_string(symbol.kind).to_string(),
+            location: sources.location(&symbol.location.uri, symbol.location.range.start, 0),
+            container: symbol.container_name.clone(),
+        })
+        .collect()
+}
+
+/// Converts a call hierarchy item and its call sites for structured output.
+///
+/// `call_sites_uri` is the file containing `from_ranges`: the caller's file for
+/// incoming calls, the queried function's file for outgoing calls.
+fn call_info(
+    sources: &mut SourceFiles,
+    item: &CallHierarchyItem,
+    call_sites_uri: &Url,
+    from_ranges: &[Range],
+) -> CallInfo {
+    CallInfo {
+        function: SymbolInfo {
+            name: item.name.clone(),
+            kind: symbol_kind_to_string(item.kind).to_string(),
+            location: sources.location(&item.uri, item.selection_range.start, 0),
+            container: None,
+        },
+        call_sites: from_ranges
+            .iter()
+            .map(|range| sources.location(call_sites_uri, range.start, 0))
+            .collect(),
+    }
+}
+
+/// Formats callers or callees with their call sites.
+fn format_calls(calls: &[CallInfo]) -> String {
+    let mut formatted = String::new();
+    for call in calls {
+        let function = &call.function;
+        let _ = writeln!(
+            formatted,
+            "\n[{}] {} - {}:{}",
+            function.kind, function.name, function.location.file_path, function.location.line
+        );
+
+        // List call sites
+        for site in &call.call_sites {
+            let _ = writeln!(
+                formatted,
+                "  Call site: line {}, column {}",
+                site.line, site.column
+            );
+        }
+    }
+    formatted
+}
+
+/// Builds a tool result with `text` as the fallback content and `output` as its
+/// structured content.
+fn structured_result<T: Serialize>(text: String, output: &T) -> Result<CallToolResult, McpError> {
+    let value = serde_json::to_value(output).map_err(|e| {
+        McpError::new(
+            ErrorCode::INTERNAL_ERROR,
+            format!("failed to serialize output: {e}"),
+            None,
+        )
+    })?;
+
+    let mut result = CallToolResult::success(vec![Content::text(text)]);
+    result.structured_content = Some(value);
+    Ok(result)
+}
+
+/// Returns the `outputSchema` declared for tools returning `T`.
+fn output_schema<T: JsonSchema + 'static>() -> Arc<JsonObject> {
+    schema_for_output::<T>().expect("tool output types serialize to JSON objects")
+}
+
+/// Formats document symbols recursively.
+#[allow(dead_code)]
+fn format_document_symbols(symbols: &[lsp_types::DocumentSymbol], indent: usize) -> String {
+    let mut result = String::new();
+    let indent_str = "  ".repeat(indent);
+
+    for symbol in symbols {
+        let kind = symbol_kind_to_string(symbol.kind);
+        let (line, _) = from_lsp_position(symbol.selection_range.start);
+        let _ = writeln!(
+            result,
+            "{}[{}] {} (line {})",
+            indent_str, kind, symbol.name, line
+        );
+
+        if let Some(children) = &symbol.children {
+            result.push_str(&format_document_symbols(children, indent + 1));
+        }
+    }
+
+    result
+}
+
+/// Formats flat symbol information.
+#[allow(dead_code)]
+fn format_symbol_information(
+    paths: &PathLabels,
+    symbols: &[lsp_types::SymbolInformation],
+) -> String {
+    let mut result = String::new();
+
+    for symbol in symbols {
+        let kind = symbol_kind_to_string(symbol.kind);
+        let file_path = paths.label_uri(&symbol.location.uri);
+        let (line, _) = from_lsp_position(symbol.location.range.start);
+
+        let container = symbol
+            .container_name
+            .as_ref()
+            .map_or_else(Default::default, |c| format!(" (in {c})"));
+
+        let _ = writeln!(
+            result,
+            "[{}] {}{} - {}:{}",
+            kind, symbol.name, container, file_path, line
+        );
+    }
+
+    result
+}
+
+/// Formats document highlights with their access kind and source line.
+///
+/// Highlights are listed in document order, preceded by a per-kind summary.
+#[allow(dead_code)]
+fn format_document_highlights(
+    paths: &PathLabels,
+    path: &Path,
+    highlights: &mut [lsp_types::DocumentHighlight],
+) -> Result<String, McpError> {
+    if highlights.is_empty() {
+        return Ok("No occurrences found.".to_string());
+    }
+
+    highlights.sort_by_key(|h| h.range.start);
+
+    let count_kind = |kind: &str| {
+        highlights
+            .iter()
+            .filter(|h| highlight_kind_to_string(h.kind) == kind)
+            .count()
+    };
+    let mut result = format!(
+        "{} occurrences in {} ({} write, {} read, {} text)\n",
+        highlights.len(),
+        paths.label(path),
+        count_kind("write"),
+        count_kind("read"),
+        count_kind("text"),
+    );
+
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        McpError::new(
+            ErrorCode::INTERNAL_ERROR,
+            format!("failed to read file: {e}"),
+            None,
+        )
+    })?;
+    let lines: Vec<_> = content.lines().collect();
+    for highlight in highlights.iter() {
+        let (line, column) = from_lsp_position(highlight.range.start);
+        let line_text = lines
+            .get(highlight.range.start.line as usize)
+            .copied()
+            .unwrap_or_default();
+        let kind = highlight_kind_to_string(highlight.kind);
+        let _ = write!(
+            result,
+            "\n[{kind}] {line}:{column}\n> {line:4} | {line_text}\n"
+        );
+    }
+
+    Ok(result)
+}
+
+/// Formats a completion item as a line with its kind, signature and import info.
+fn format_completion_item(item: &lsp_types::CompletionItem) -> String {
+    let kind = item.kind.map_or("unknown", completion_kind_to_string);
+    let mut result = format!("[{kind}] {}", item.label);
+
+    let label_details = item.label_details.as_ref();
+    if let Some(detail) = label_details.and_then(|d| d.detail.as_ref()) {
+        result.push_str(detail);
+    }
+    if let Some(signature) = label_details
+        .and_then(|d| d.description.as_ref())
+        .or(item.detail.as_ref())
+    {
+        let _ = write!(result, " - {signature}");
+    }
+
+    // Edits applied on accept, e.g. the `use` statement for an item not yet imported
+    for edit in item.additional_text_edits.iter().flatten() {
+        let text = edit.new_text.trim();
+        if !text.is_empty() {
+            let _ = write!(result, "\n    on accept: {text}");
+        }
+    }
+
+    result
+}
+
+/// Returns the display text of an inlay hint label.
+fn inlay_hint_label(label: &lsp_types::InlayHintLabel) -> String {
+    match label {
+        lsp_types::InlayHintLabel::String(s) => s.clone(),
+        lsp_types::InlayHintLabel::LabelParts(parts) => {
+            parts.iter().map(|part| part.value.as_str()).collect()
+        }
+    }
+}
+
+/// Renders a range of source lines with inlay hints inlined.
+///
+/// Hints are written as `/* ... */` comments so the rendered text stays valid
+/// source if it is copied back into an edit. Lines are 1-indexed and inclusive.
+#[allow(dead_code)]
+fn render_inlay_hints(
+    content: &str,
+    start_line: u32,
+    end_line: u32,
+    hints: &[lsp_types::InlayHint],
+) -> String {
+    let start = start_line.saturating_sub(1) as usize;
+    let end = end_line as usize;
+
+    let mut result = String::new();
+    for (line_idx, line_text) in content.lines().enumerate().take(end).skip(start) {
+        let mut line_hints: Vec<_> = hints
+            .iter()
+            .filter(|hint| hint.position.line as usize == line_idx)
+            .collect();
+        // Insert from the end of the line so earlier byte offsets stay valid
+        line_hints.sort_by_key(|hint| std::cmp::Reverse(hint.position.character));
+
+        let mut rendered = line_text.to_string();
+        for hint in line_hints {
+            let offset = utf16_offset_to_byte(line_text, hint.position.character);
+            let pad_left = if hint.padding_left == Some(true) {
+                " "
+            } else {
+                ""
+            };
+            let pad_right = if hint.padding_right == Some(true) {
+                " "
+            } else {
+                ""
+            };
+            let text = format!("{pad_left}/*{}*/{pad_right}", inlay_hint_label(&hint.label));
+            rendered.insert_str(offset, &text);
+        }
+
+        let _ = writeln!(result, "  {:4} | {rendered}", line_idx + 1);
+    }
+    result
+}
+
+/// Formats a type hierarchy item as a single line.
+fn format_type_hierarchy_item(paths: &PathLabels, item: &TypeHierarchyItem) -> String {
+    let kind = symbol_kind_to_string(item.kind);
+    let file_path = paths.label_uri(&item.uri);
+    let (line, _) = from_lsp_position(item.selection_range.start);
+    format!("[{kind}] {} - {file_path}:{line}", item.name)
+}
+
+/// Builds a type hierarchy item from the document symbol declaring it.
+fn symbol_type_hierarchy_item(uri: &Url, symbol: &DocumentSymbol) -> TypeHierarchyItem {
+    TypeHierarchyItem {
+        name: symbol.name.clone(),
+        kind: symbol.kind,
+        tags: None,
+        detail: symbol.detail.clone(),
+        uri: uri.clone(),
+        range: symbol.range,
+        selection_range: symbol.selection_range,
+        data: None,
+    }
+}
+
+/// Returns the byte offsets in `header` of the supertraits a trait declares.
+///
+/// `header` is the declaration following the trait's name, e.g.
+/// `<T>: fmt::Display + Add<T> where T: Copy {`. Each offset points at the last
+/// segment of a bound's path; lifetimes and `?Sized` bounds are skipped.
+fn supertrait_offsets(header: &str) -> Vec<usize> {
+    let mut depth = 0_usize;
+    let mut colon = None;
+    let mut list_end = header.len();
+    for (i, ch) in header.char_indices() {
+        match ch {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth = depth.saturating_sub(1),
+            ':' if depth == 0 && colon.is_none() => colon = Some(i + 1),
+            '{' | ';' if depth == 0 => {
+                list_end = i;
+                break;
+            }
+            'w' if depth == 0
+                && header[i..].starts_with("where")
+                && !header[..i].ends_with(|c: char| c.is_alphanumeric() || c == '_') =>
+            {
+                list_end = i;
+                break;
+            }
+            _ => {}
+        }
+    }
+    let Some(colon) = colon else {
+        return Vec::new();
+    };
+
+    let mut offsets = Vec::new();
+    let mut bound_start = colon;
+    depth = 0;
+    for (i, ch) in header[colon..list_end]
+        .char_indices()
+        .map(|(i, ch)| (colon + i, ch))
+        .chain(std::iter::once((list_end, '+')))
+    {
+        match ch {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth = depth.saturating_sub(1),
+            '+' if depth == 0 => {
+                let bound = &header[bound_start..i];
+                let trimmed = bound.trim_start();
+                let path_end = trimmed.find(['<', '(']).unwrap_or(trimmed.len());
+                let path = trimmed[..path_end].trim_end();
+                if !path.is_empty() && !path.starts_with(['\'', '?']) {
+                    let segment = path.rfind("::").map_or(0, |pos| pos + 2);
+                    offsets.push(bound_start + (bound.len() - trimmed.len()) + segment);
+                }
+                bound_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    offsets
+}
+
+/// Converts a byte offset into `content` to an LSP position.
+fn offset_to_position(content: &str, offset: usize) -> Position {
+    let prefix = &content[..offset];
+    let line_start = prefix.rfind('\n').map_or(0, |pos| pos + 1);
+    let line = u32::try_from(prefix.matches('\n').count()).unwrap_or(u32::MAX);
+    Position::new(
+        line,
+        byte_to_utf16_offset(&content[line_start..], offset - line_start),
+    )
+}
+
+/// Formats a runnable as its label and location followed by the command that runs it.
+fn format_runnable(paths: &PathLabels, runnable: &Runnable) -> String {
+    let mut result = runnable.label.clone();
+    if let Some(location) = &runnable.location {
+        let file_path = paths.label_uri(&location.target_uri);
+        let (line, _) = from_lsp_position(location.target_selection_range.start);
+        let _ = write!(result, " - {file_path}:{line}");
+    }
+    let _ = write!(result, "\n    $ {}", runnable.command_line());
+    result
+}
+
+/// Reads the `mod` declaration at a location.
+///
+/// Returns the module name and the declaration as written up to the name, e.g.
+/// `("calculator", "pub mod calculator")`.
+#[allow(dead_code)]
+fn read_module_declaration(location: &lsp_types::Location) -> Option<(String, String)> {
+    let path = location.uri.to_file_path().ok()?;
+    let content = std::fs::read_to_string(path).ok()?;
+    let line = content.lines().nth(location.range.start.line as usize)?;
+
+    let start = utf16_offset_to_byte(line, location.range.start.character);
+    let end = if location.range.end.line == location.range.start.line {
+        utf16_offset_to_byte(line, location.range.end.character)
+    } else {
+        line.len()
+    };
+    let name = line.get(start..end)?.to_string();
+    let declaration = format!("{} {name}", line[..start].trim());
+
+    Some((name, declaration))
+}
+
+/// Formats a submodule as its declaration followed by the file holding its contents.
+#[allow(dead_code)]
+fn format_module_line(
+    paths: &PathLabels,
+    declaration: &lsp_types::Location,
+    module_path: &Path,
+) -> String {
+    let written = read_module_declaration(declaration)
+        .map_or_else(|| "mod <unknown>".to_string(), |(_, written)| written);
+    let declaration_path = declaration.uri.to_file_path().ok();
+
+    if declaration_path.as_deref() == Some(module_path) {
+        let (line, _) = from_lsp_position(declaration.range.start);
+        format!("{written} (inline) - {}:{line}", paths.label(module_path))
+    } else {
+        format!("{written} - {}", paths.label(module_path))
+    }
+}
+
+/// Returns the 1-indexed (line, column) of the end of `content`.
+///
+/// Positions there belong to the file's own module rather than to any item in it.
+fn end_of_file_position(content: &str) -> (u32, u32) {
+    let line = content.matches('\n').count();
+    let last_line = content.rsplit('\n').next().unwrap_or_default();
+    let column = last_line.encode_utf16().count();
+    (
+        u32::try_from(line).unwrap_or(u32::MAX - 1) + 1,
+        u32::try_from(column).unwrap_or(u32::MAX - 1) + 1,
+    )
+}
+
+/// Collects the text edits of a workspace edit, grouped per document.
+///
+/// Resource operations (create, rename, delete) carry no text edits and are skipped.
+#[allow(dead_code)]
+fn workspace_edit_text_edits(edit: WorkspaceEdit) -> Vec<(Url, Vec<TextEdit>)> {
+    if let Some(document_changes) = edit.document_changes {
+        let document_edits = match document_changes {
+            DocumentChanges::Edits(edits) => edits,
+            DocumentChanges::Operations(operations) => operations
+                .into_iter()
+                .filter_map(|operation| match operation {
+                    DocumentChangeOperation::Edit(edit) => Some(edit),
+                    DocumentChangeOperation::Op(_) => None,
+                })
+                .collect(),
+        };
+        return document_edits
+            .into_iter()
+            .map(|document_edit| {
+                let edits = document_edit
+                    .edits
+                    .into_iter()
+                    .map(|edit| match edit {
+                        OneOf::Left(edit) => edit,
+                        OneOf::Right(annotated) => annotated.text_edit,
+                    })
+                    .collect();
+                (document_edit.text_document.uri, edits)
+            })
+            .collect();
+    }
+
+    let mut changes: Vec<_> = edit.changes.unwrap_or_default().into_iter().collect();
+    changes.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+    changes
+}
+
+/// Converts an LSP position to a byte offset into `lines[first..]` joined with newlines.
+fn position_to_offset(lines: &[&str], first: usize, position: Position) -> usize {
+    let line = (position.line as usize).clamp(first, lines.len() - 1);
+    let preceding: usize = lines[first..line].iter().map(|l| l.len() + 1).sum();
+    preceding + utf16_offset_to_byte(lines[line], position.character)
+}
+
+/// Renders text edits to one file as a unified diff.
+///
+/// Edits touching the same lines are combined into one hunk. Hunks show only the
+/// changed lines, without surrounding context.
+fn format_edit_diff(label: &str, content: &str, edits: &[TextEdit]) -> String {
+    let lines: Vec<&str> = content.split('\n').collect();
+    let last_line = lines.len() - 1;
+
+    let mut edits = edits.to_vec();
+    edits.sort_by_key(|edit| (edit.range.start.line, edit.range.start.character));
+
+    let mut result = format!("--- a/{label}\n+++ b/{label}\n");
+    let mut line_delta: isize = 0;
+    let mut group_start = 0;
+    while group_start < edits.len() {
+        // Group edits whose line spans overlap
+        let first = (edits[group_start].range.start.line as usize).min(last_line);
+        let mut last = (edits[group_start].range.end.line as usize).min(last_line);
+        let mut group_end = group_start + 1;
+        while group_end < edits.len() && edits[group_end].range.start.line as usize <= last {
+            last = last.max((edits[group_end].range.end.line as usize).min(last_line));
+            group_end += 1;
+        }
+
+        let old = lines[first..=last].join("\n");
+        let mut new = old.clone();
+        for edit in edits[group_start..group_end].iter().rev() {
+            let start = position_to_offset(&lines, first, edit.range.start);
+            let end = position_to_offset(&lines, first, edit.range.end).max(start);
+            if end <= new.len() && new.is_char_boundary(start) && new.is_char_boundary(end) {
+                new.replace_range(start..end, &edit.new_text);
+            }
+        }
+
+        let old_lines: Vec<&str> = old.split('\n').collect();
+        let new_lines: Vec<&str> = new.split('\n').collect();
+        let new_first = first.saturating_add_signed(line_delta);
+        let _ = writeln!(
+            result,
+            "@@ -{},{} +{},{} @@",
+            first + 1,
+            old_lines.len(),
+            new_first + 1,
+            new_lines.len()
+        );
+        for line in &old_lines {
+            let _ = writeln!(result, "-{line}");
+        }
+        for line in &new_lines {
+            let _ = writeln!(result, "+{line}");
+        }
+
+        line_delta += new_lines.len().cast_signed() - old_lines.len().cast_signed();
+        group_start = group_end;
+    }
+
+    result
+}
+
+/// Extracts the value of the `label` attribute from a DOT statement.
+fn dot_label(statement: &str) -> Option<&str> {
+    let start = statement.find("[label=\"")? + "[label=\"".len();
+    let end = statement[start..].find('"')? + start;
+    Some(&statement[start..end])
+}
+
+/// Parses the DOT crate graph produced by rust-analyzer's `viewCrateGraph`.
+///
+/// Only the node and edge statements rust-analyzer emits are understood. Crates are
+/// sorted by name and dependencies by the names of their endpoints.
+#[allow(dead_code)]
+fn parse_crate_graph_dot(dot: &str) -> CrateGraph {
+    let mut graph = CrateGraph::default();
+
+    for statement in dot.lines().map(str::trim) {
+        if let Some((from, rest)) = statement.split_once(" -> ") {
+            let to = rest.split(['[', ';']).next().unwrap_or_default().trim();
+            graph.dependencies.push(CrateDependency {
+                from: from.trim().to_string(),
+                to: to.to_string(),
+                name: dot_label(rest)
+                    .filter(|label| !label.is_empty())
+                    .map(str::to_string),
+            });
+        } else if let Some(name) = dot_label(statement) {
+            let id = statement.split('[').next().unwrap_or_default().trim();
+            graph.crates.push(CrateNode {
+                id: id.to_string(),
+                name: name.to_string(),
+            });
+        }
+    }
+
+    let names: HashMap<String, String> = graph
+        .crates
+        .iter()
+        .map(|node| (node.id.clone(), node.name.clone()))
+        .collect();
+    let name_of = |id: &str| names.get(id).cloned().unwrap_or_default();
+    graph
+        .crates
+        .sort_by(|a, b| (&a.name, &a.id).cmp(&(&b.name, &b.id)));
+    graph.dependencies.sort_by_key(|dep| {
+        (
+            name_of(&dep.from),
+            dep.from.clone(),
+            name_of(&dep.to),
+            dep.to.clone(),
+        )
+    });
+
+    graph
+}
+
+/// Renders a crate graph as a Mermaid flowchart.
+#[allow(dead_code)]
+fn format_crate_graph_mermaid(graph: &CrateGraph) -> String {
+    // Mermaid node ids must not start with an underscore, so prefix DOT ids
+    let node_id = |id: &str| format!("crate{}", id.trim_start_matches('_'));
+
+    let mut result = String::from("graph LR\n");
+    for node in &graph.crates {
+        let _ = writeln!(result, "    {}[\"{}\"]", node_id(&node.id), node.name);
+    }
+    for dep in &graph.dependencies {
+        match &dep.name {
+            Some(name) => {
+                let _ = writeln!(
+                    result,
+                    "    {} -->|{name}| {}",
+                    node_id(&dep.from),
+                    node_id(&dep.to)
+                );
+            }
+            None => {
+                let _ = writeln!(
+                    result,
+                    "    {} --> {}",
+                    node_id(&dep.from),
+                    node_id(&dep.to)
+                );
+            }
+        }
+    }
+    result
+}
+
+/// Syntax kinds treated as items when choosing which part of a syntax tree to show.
+const ITEM_SYNTAX_KINDS: &[&str] = &[
+    "CONST",
+    "ENUM",
+    "EXTERN_BLOCK",
+    "EXTERN_CRATE",
+    "FN",
+    "IMPL",
+    "MACRO_DEF",
+    "MACRO_RULES",
+    "MODULE",
+    "STATIC",
+    "STRUCT",
+    "TRAIT",
+    "TYPE_ALIAS",
+    "UNION",
+    "USE",
+];
+
+/// Finds the innermost item node containing a 0-indexed (line, column).
+#[allow(dead_code)]
+fn find_enclosing_item(node: &SyntaxTreeNode, line: u32, column: u32) -> Option<&SyntaxTreeNode> {
+    let position = (line, column);
+    if position < (node.start[1], node.start[2]) || position > (node.end[1], node.end[2]) {
+        return None;
+    }
+
+    node.children
+        .iter()
+        .find_map(|child| find_enclosing_item(child, line, column))
+        .or_else(|| {
+            ITEM_SYNTAX_KINDS
+                .contains(&node.kind.as_str())
+                .then_some(node)
+        })
+}
+
+/// Renders a syntax tree one node per line, indented by depth.
+///
+/// Nodes show their kind and 1-indexed span; tokens show their kind and source text.
+#[allow(dead_code)]
+fn render_syntax_tree(node: &SyntaxTreeNode, content: &str, depth: usize, out: &mut String) {
+    let indent = "  ".repeat(depth);
+    if node.children.is_empty() && node.node_type == "Token" {
+        let text = content
+            .get(node.start[0] as usize..node.end[0] as usize)
+            .unwrap_or_default();
+        let _ = writeln!(out, "{indent}{} {text:?}", node.kind);
+        return;
+    }
+
+    let _ = writeln!(
+        out,
+        "{indent}{}@{}:{}..{}:{}",
+        node.kind,
+        node.start[1] + 1,
+        node.start[2] + 1,
+        node.end[1] + 1,
+        node.end[2] + 1
+    );
+    for child in &node.children {
+        render_syntax_tree(child, content, depth + 1, out);
+    }
+}
+
+/// Finds the innermost symbol whose range contains a position, formatted as `[kind] name`.
+#[allow(dead_code)]
+fn enclosing_symbol(symbols: &DocumentSymbolResponse, position: Position) -> Option<String> {
+    let contains = |range: &Range| range.start <= position && position <= range.end;
+
+    match symbols {
+        DocumentSymbolResponse::Nested(symbols) => {
+            let mut innermost = None;
+            let mut level = symbols.as_slice();
+            while let Some(symbol) = level.iter().find(|symbol| contains(&symbol.range)) {
+                innermost = Some(symbol);
+                level = symbol.children.as_deref().unwrap_or_default();
+            }
+            innermost
+                .map(|symbol| format!("[{}] {}", symbol_kind_to_string(symbol.kind), symbol.name))
+        }
+        DocumentSymbolResponse::Flat(symbols) => symbols
+            .iter()
+            .filter(|symbol| contains(&symbol.location.range))
+            .min_by_key(|symbol| {
+                let range = symbol.location.range;
+                (range.end.line - range.start.line, range.end.character)
+            })
+            .map(|symbol| format!("[{}] {}", symbol_kind_to_string(symbol.kind), symbol.name)),
+    }
+}
+
+/// Formats a diagnostic as `severity[code] line:col: message`.
+///
+/// Continuation lines of multi-line messages are indented under the first line.
+#[allow(dead_code)]
+fn format_diagnostic(diagnostic: &Diagnostic) -> String {
+    let severity = diagnostic_severity_to_string(diagnostic.severity);
+    let code = match &diagnostic.code {
+        Some(NumberOrString::Number(code)) => format!("[{code}]"),
+        Some(NumberOrString::String(code)) => format!("[{code}]"),
+        None => String::new(),
+    };
+    let (line, column) = from_lsp_position(diagnostic.range.start);
+    let message = diagnostic.message.trim_end().replace('\n', "\n      ");
+    format!("{severity}{code} {line}:{column}: {message}")
+}
+
+/// Formats the documentation URLs of an item, noting local docs that were not built yet.
+fn format_external_docs(paths: &PathLabels, docs: &ExternalDocsPair) -> String {
+    let mut formatted = String::new();
+    if let Some(web) = &docs.web {
+        let _ = writeln!(formatted, "Web: {web}");
+    }
+    if let Some(local) = &docs.local
+        && let Ok(path) = local.to_file_path()
+    {
+        let fragment = local
+            .fragment()
+            .map(|f| format!("#{f}"))
+            .unwrap_or_default();
+        if path.exists() {
+            let _ = writeln!(formatted, "Local: {}{fragment}", paths.label(&path));
+        } else {
+            let _ = writeln!(
+                formatted,
+                "Local: not built; run `cargo doc` to generate {}",
+                paths.label(&path)
+            );
+        }
+    }
+    if formatted.is_empty() {
+        formatted.push_str("No documentation found for the item at this position.");
+    }
+    formatted
+}
+
+/// Tool implementations for `KadabraRunes`.
+#[tool_router]
+impl KadabraRunes {
+    /// Jump to the definition of a symbol at a given position or by name.
+    #[tool(
+        description = "Jump to where a symbol is defined. Essential for tracing imports and understanding implementations.",
+        output_schema = output_schema::<LocationsResult>()
+    )]
+    pub async fn goto_definition(
+        &self,
+        Parameters(params): Parameters<GotoParams>,
+    ) -> Result<CallToolResult, McpError> {
+        // Extract position from params
+        let ResolvedPosition {
+            file_path,
+            line,
+            column,
+            note,
+        } = self.resolve_position(&params.position).await?;
+
+        // Ensure the document is open
+        self.lsp_client.did_open(&file_path).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to open document: {e}"),
+                None,
+            )
+        })?;
+
+        // Call LSP client
+        let response = self
+            .lsp_client
+            .goto_definition(&file_path, line, column)
+            .await
+            .map_err(|e| {
+                McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("goto_definition failed: {e}"),
+                    None,
+                )
+            })?;
+
+        // Convert response to locations
+        let locations = goto_response_to_locations(response);
+
+        // Read locations with context
+        let output = LocationsResult {
+            locations: self.locations_in_context(&locations, params.context).await,
+            page: None,
+            summary: None,
+        };
+        let formatted = format_locations(&output.locations);
+
+        structured_result(with_note(note.as_deref(), formatted), &output)
+    }
+
+    /// Jump to the declaration of a symbol at a given position.
+    #[tool(
+        description = "Jump to where a symbol is declared, as opposed to defined. Resolves an impl method to its trait method, or a C/C++ definition to its header declaration.",
+        output_schema = output_schema::<LocationsResult>()
+    )]
+    async fn goto_declaration(
+        &self,
+        Parameters(params): Parameters<GotoParams>,
+    ) -> Result<CallToolResult, McpError> {
+        // Extract position from params
+        let ResolvedPosition {
+            file_path,
+            line,
+            column,
+            note,
+        } = self.resolve_position(&params.position).await?;
+
+        // Ensure the document is open
+        self.lsp_client.did_open(&file_path).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to open document: {e}"),
+                None,
+            )
+        })?;
+
+        // Call LSP client
+        let response = self
+            .lsp_client
+            .goto_declaration(&file_path, line, column)
+            .await
+            .map_err(|e| {
+                McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("goto_declaration failed: {e}"),
+                    None,
+                )
+            })?;
+
+        // Convert response to locations
+        let locations = goto_response_to_locations(response);
+
+        // Read locations with context
+        let output = LocationsResult {
+            locations: self.locations_in_context(&locations, params.context).await,
+            page: None,
+            summary: None,
+        };
+        let formatted = format_locations(&output.locations);
+
+        structured_result(with_note(note.as_deref(), formatted), &output)
+    }
+
+    /// Find all references to a symbol in the workspace.
+    #[tool(
+        description = "Find all usages of a symbol. Reveals dependencies, call sites, and impact of changes.",
+        output_schema = output_schema::<LocationsResult>()
+    )]
+    pub async fn find_references(
+        &self,
+        Parameters(params): Parameters<FindReferencesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        // Extract position from params
+        let ResolvedPosition {
+            file_path,
+            line,
+            column,
+            note,
+        } = self.resolve_symbol_query(&params.query).await?;
+
+        // Ensure the document is open
+        self.lsp_client.did_open(&file_path).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to open document: {e}"),
+                None,
+            )
+        })?;
+
+        // Call LSP client
+        let locations = self
+            .lsp_client
+            .find_references(&file_path, line, column, params.include_declaration)
+            .await
+            .map_err(|e| {
+                McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("find_references failed: {e}"),
+                    None,
+                )
+            })?;
+
+        // Group references by file and enclosing item, keeping the filtered and expanded groups
+        let references: Vec<_> = self
+            .group_references(locations)
+            .await
+            .into_iter()
+            .filter(|reference| {
+                (params.filter.include_tests || !reference.in_tests)
+                    && self.passes_path_filter(&reference.location.uri, &params.filter)
+                    && in_reference_group(
+                        reference,
+                        params.group_file.as_deref(),
+                        params.group_item.as_deref(),
+                    )
+            })
+            .collect();
+        let summary = summarize_references(&references);
+
+        if params.summary_only {
+            let formatted = if summary.total == 0 {
+                "No results found.".to_string()
+            } else {
+                let text = format!(
+                    "{}\nPass groupFile and groupItem to list the references of a group.\n",
+                    format_reference_summary(&summary)
+                );
+                truncate_output(&text, params.page.max_output_chars as usize)
+            };
+            let output = LocationsResult {
+                locations: Vec::new(),
+                page: None,
+                summary: Some(summary),
+            };
+            return structured_result(with_note(note.as_deref(), formatted), &output);
+        }
+
+        // Cut the requested page and read its context
+        let (references, mut page) =
+            paginate(references, &params.page, DEFAULT_PAGE_LIMIT, |reference| {
+                reference.file_path.clone()
+            })?;
+        let page_locations: Vec<_> = references
+            .iter()
+            .map(|reference| reference.location.clone())
+            .collect();
+        let mut locations = self
+            .locations_in_context(&page_locations, params.context)
+            .await;
+        let rendered = render_page(
+            &mut locations,
+            &mut page,
+            params.page.max_output_chars,
+            format_location,
+        );
+        let formatted = if page.total == 0 {
+            "No results found.".to_string()
+        } else {
+            format_grouped_references(&summary, &page, &references, &rendered)
+        };
+        let output = LocationsResult {
+            locations,
+            page: Some(page),
+            summary: Some(summary),
+        };
+
+        structured_result(with_note(note.as_deref(), formatted), &output)
+    }
+
+    /// Get type information and documentation for a symbol.
+    #[tool(
+        description = "Get type signature and docs. Quick way to understand what something is without navigating away.",
+        output_schema = output_schema::<HoverResult>()
+    )]
+    async fn hover(
+        &self,
+        Parameters(params): Parameters<HoverParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let ResolvedPosition {
+            file_path,
+            line,
+            column,
+            note,
+        } = self.resolve_position(&params.position).await?;
+
+        // Ensure the document is open
+        self.lsp_client.did_open(&file_path).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to open document: {e}"),
+                None,
+            )
+        })?;
+
+        // Call LSP client
+        let hover_result = self
+            .lsp_client
+            .hover(&file_path, line, column)
+            .await
+            .map_err(|e| {
+                McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("hover failed: {e}"),
+                    None,
+                )
+            })?;
+
+        // Format hover information
+        let text = hover_result.map(|hover| extract_hover_text(hover.contents));
+        let (formatted, output) = match text {
+            Some(text) if !text.is_empty() => {
+                let output = parse_hover_text(&text);
+                (text, output)
+            }
+            _ => (
+                "No hover information available.".to_string(),
+                HoverResult::default(),
+            ),
+        };
+
+        structured_result(with_note(note.as_deref(), formatted), &output)
+    }
+
+    /// List all symbols defined in a file.
+    #[tool(
+        description = "List all symbols in a file. Get a structural overview: functions, types, constants, etc.",
+        output_schema = output_schema::<SymbolsResult>()
+    )]
+    async fn document_symbols(
+        &self,
+        Parameters(params): Parameters<DocumentSymbolsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let file_path = self.input_path(&params.file_path)?;
+
+        // Ensure the document is open
+        self.lsp_client.did_open(&file_path).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to open document: {e}"),
+                None,
+            )
+        })?;
+
+        // Call LSP client
+        let response = self
+            .lsp_client
+            .document_symbols(&file_path)
+            .await
+            .map_err(|e| {
+                McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("document_symbols failed: {e}"),
+                    None,
+                )
+            })?;
+
+        // Format symbols
+        let mut output = SymbolsResult::default();
+        let formatted = match response {
+            DocumentSymbolResponse::Flat(symbols) => {
+                output.symbols = symbol_information_infos(&self.paths, &symbols);
+                format_symbol_information(&self.paths, &symbols)
+            }
+            DocumentSymbolResponse::Nested(symbols) => {
+                let content = std::fs::read_to_string(&file_path).unwrap_or_default();
+                let label = self.paths.label(&file_path);
+                document_symbol_infos(&label, &content, &symbols, None, &mut output.symbols);
+                format_document_symbols(&symbols, 0)
+            }
+        };
+        let formatted = if output.symbols.is_empty() {
+            "No symbols found in document.".to_string()
+        } else {
+            formatted
+        };
+
+        structured_result(formatted, &output)
+    }
+
+    /// Search for symbols across the entire workspace.
+    #[tool(
+        description = "Search symbols by name across the workspace. Find types, functions, or modules without knowing their location.",
+        output_schema = output_schema::<SymbolsResult>()
+    )]
+    async fn workspace_symbols(
+        &self,
+        Parameters(params): Parameters<WorkspaceSymbolsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        // Call LSP client
+        let symbols = self
+            .lsp_client
+            .workspace_symbols(&params.query)
+            .await
+            .map_err(|e| {
+                McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("workspace_symbols failed: {e}"),
+                    None,
+                )
+            })?;
+
+        // Filter and cut the requested page
+        let symbols = self
+            .filter_results(symbols, &params.filter, |symbol| {
+                (&symbol.location.uri, symbol.location.range.start)
+            })
+            .await;
+        let (mut symbols, mut page) =
+            paginate(symbols, &params.page, params.max_results, |symbol| {
+                self.paths.label_uri(&symbol.location.uri)
+            })?;
+        let rendered = render_page(
+            &mut symbols,
+            &mut page,
+            params.page.max_output_chars,
+            |symbol| format_symbol_information(&self.paths, std::slice::from_ref(symbol)),
+        );
+
+        // Format symbols
+        let formatted = if page.total == 0 {
+            format!("No symbols found matching '{}'.", params.query)
+        } else {
+            format_page(&page, &rendered, "")
+        };
+        let output = SymbolsResult {
+            symbols: symbol_information_infos(&self.paths, &symbols),
+            page: Some(page),
+        };
+
+        structured_result(formatted, &output)
+    }
+
+    /// Find all functions that call the function at the given position.
+    #[tool(
+        description = "Find callers of a function. Build upward call graphs, trace who depends on this code.",
+        output_schema = output_schema::<CallsResult>()
+    )]
+    async fn incoming_calls(
+        &self,
+        Parameters(params): Parameters<IncomingCallsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let ResolvedPosition {
+            file_path,
+            line,
+            column,
+            note,
+        } = self.resolve_position(&params.position).await?;
+
+        // Ensure the document is open
+        self.lsp_client.did_open(&file_path).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to open document: {e}"),
+                None,
+            )
+        })?;
+
+        // Call LSP client
+        let calls = self
+            .lsp_client
+            .incoming_calls(&file_path, line, column)
+            .await
+            .map_err(|e| {
+                McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("incoming_calls failed: {e}"),
+                    None,
+                )
+            })?;
+
+        // Filter callers and format call hierarchy
+        let calls = self
+            .filter_results(calls, &params.filter, |call| {
+                (&call.from.uri, call.from.selection_range.start)
+            })
+            .await;
+        let (calls, mut page) = paginate(calls, &params.page, DEFAULT_PAGE_LIMIT, |call| {
+            self.paths.label_uri(&call.from.uri)
+        })?;
+        let mut sources = SourceFiles::new(&self.paths);
+        let mut calls: Vec<_> = calls
+            .iter()
+            .map(|call| call_info(&mut sources, &call.from, &call.from.uri, &call.from_ranges))
+            .collect();
+        let rendered = render_page(
+            &mut calls,
+            &mut page,
+            params.page.max_output_chars,
+            |call| format_calls(std::slice::from_ref(call)),
+        );
+        let formatted = if page.total == 0 {
+            "No incoming calls found.".to_string()
+        } else {
+            format_page(&page, &rendered, "")
+        };
+        let output = CallsResult {
+            calls,
+            page: Some(page),
+        };
+
+        structured_result(with_note(note.as_deref(), formatted), &output)
+    }
+
+    /// Find all functions called by the function at the given position.
+    #[tool(
+        description = "Find callees of a function. Build downward call graphs, trace execution flow.",
+        output_schema = output_schema::<CallsResult>()
+    )]
+    async fn outgoing_calls(
+        &self,
+        Parameters(params): Parameters<OutgoingCallsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let ResolvedPosition {
+            file_path,
+            line,
+            column,
+            note,
+        } = self.resolve_position(&params.position).await?;
+
+        // Ensure the document is open
+        self.lsp_client.did_open(&file_path).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to open document: {e}"),
+                None,
+            )
+        })?;
+
+        // Call LSP client
+        let calls = self
+            .lsp_client
+            .outgoing_calls(&file_path, line, column)
+            .await
+            .map_err(|e| {
+                McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("outgoing_calls failed: {e}"),
+                    None,
+                )
+            })?;
+
+        // Format call hierarchy
+        let uri = path_to_url(&file_path).map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("invalid file path: {e}"),
+                None,
+            )
+        })?;
+        let (calls, mut page) = paginate(calls, &params.page, DEFAULT_PAGE_LIMIT, |call| {
+            self.paths.label_uri(&call.to.uri)
+        })?;
+        let mut sources = SourceFiles::new(&self.paths);
+        let mut calls: Vec<_> = calls
+            .iter()
+            .map(|call| call_info(&mut sources, &call.to, &uri, &call.from_ranges))
+            .collect();
+        let rendered = render_page(
+            &mut calls,
+            &mut page,
+            params.page.max_output_chars,
+            |call| format_calls(std::slice::from_ref(call)),
+        );
+        let formatted = if page.total == 0 {
+            "No outgoing calls found.".to_string()
+        } else {
+            format_page(&page, &rendered, "")
+        };
+        let output = CallsResult {
+            calls,
+            page: Some(page),
+        };
+
+        structured_result(with_note(note.as_deref(), formatted), &output)
+    }
+
+    /// Find all implementations of a trait or interface.
+    #[tool(
+        description = "Find trait/interface implementations. Discover concrete types, understand polymorphism.",
+        output_schema = output_schema::<LocationsResult>()
+    )]
+    pub async fn implementations(
+        &self,
+        Parameters(params): Parameters<ImplementationsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        // Extract position from params
+        let ResolvedPosition {
+            file_path,
+            line,
+            column,
+            note,
+        } = self.resolve_symbol_query(&params.query).await?;
+
+        // Ensure the document is open
+        self.lsp_client.did_open(&file_path).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to open document: {e}"),
+                None,
+            )
+        })?;
+
+        // Call LSP client
+        let response = self
+            .lsp_client
+            .implementations(&file_path, line, column)
+            .await
+            .map_err(|e| {
+                McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("implementations failed: {e}"),
+                    None,
+                )
+            })?;
+
+        // Convert response to locations and filter them
+        let locations = goto_response_to_locations(response);
+        let locations = self
+            .filter_results(locations, &params.filter, |loc| (&loc.uri, loc.range.start))
+            .await;
+
+        // Cut the requested page and read its context
+        let (locations, mut page) = paginate(locations, &params.page, DEFAULT_PAGE_LIMIT, |loc| {
+            self.paths.label_uri(&loc.uri)
+        })?;
+        let mut locations = self.locations_in_context(&locations, params.context).await;
+        let rendered = render_page(
+            &mut locations,
+            &mut page,
+            params.page.max_output_chars,
+            format_location,
+        );
+        let formatted = if page.total == 0 {
+            "No results found.".to_string()
+        } else {
+            format_page(&page, &rendered, "\n\n---\n\n")
+        };
+        let output = LocationsResult {
+            locations,
+            page: Some(page),
+            summary: None,
+        };
+
+        structured_result(with_note(note.as_deref(), formatted), &output)
+    }
+
+    /// Jump to the type definition of a symbol.
+    #[tool(
+        description = "Jump to a symbol's type definition. Understand what type a variable or expression has.",
+        output_schema = output_schema::<LocationsResult>()
+    )]
+    async fn type_definition(
+        &self,
+        Parameters(params): Parameters<TypeDefinitionParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let ResolvedPosition {
+            file_path,
+            line,
+            column,
+            note,
+        } = self.resolve_position(&params.position).await?;
+
+        // Ensure the document is open
+        self.lsp_client.did_open(&file_path).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to open document: {e}"),
+                None,
+            )
+        })?;
+
+        // Call LSP client
+        let response = self
+            .lsp_client
+            .type_definition(&file_path, line, column)
+            .await
+            .map_err(|e| {
+                McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("type_definition failed: {e}"),
+                    None,
+                )
+            })?;
+
+        // Convert response to locations
+        let locations = goto_response_to_locations(response);
+
+        // Read locations with context
+        let output = LocationsResult {
+            locations: self.locations_in_context(&locations, params.context).await,
+            page: None,
+            summary: None,
+        };
+        let formatted = format_locations(&output.locations);
+
+        structured_result(with_note(note.as_deref(), formatted), &output)
+    }
+
+    /// Show the supertypes and subtypes of the type at the given position.
+    #[tool(
+        description = "Show a type's hierarchy: supertypes/supertraits above and subtypes/implementors below, expanded to a given depth."
+    )]
+    pub async fn type_hierarchy(
+        &self,
+        Parameters(params): Parameters<TypeHierarchyParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let ResolvedPosition {
+            file_path,
+            line,
+            column,
+            note,
+        } = self.resolve_position(&params.position).await?;
+
+        // Ensure the document is open
+        self.lsp_client.did_open(&file_path).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to open document: {e}"),
+                None,
+            )
+        })?;
+
+        // Call LSP client, falling back to declarations when the server (like
+        // rust-analyzer) has no type hierarchy support
+        let (items, declared) = match self
+            .lsp_client
+            .prepare_type_hierarchy(&file_path, line, column)
+            .await
+        {
+            Ok(items) => (items, false),
+            Err(LspError::CapabilityNotSupported(_)) => {
+                let response = self
+                    .lsp_client
+                    .goto_definition(&file_path, line, column)
+                    .await
+                    .map_err(|e| {
+                        McpError::new(
+                            ErrorCode::INTERNAL_ERROR,
+                            format!("type_hierarchy failed: {e}"),
+                            None,
+                        )
+                    })?;
+                let definition = goto_response_to_locations(response).into_iter().next();
+                let item = match definition {
+                    Some(location) => {
+                        self.declared_type_item(&location.uri, location.range.start)
+                            .await?
+                    }
+                    None => None,
+                };
+                (item.into_iter().collect(), true)
+            }
+            Err(e) => {
+                return Err(McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("type_hierarchy failed: {e}"),
+                    None,
+                ));
+            }
+        };
+        let note = if declared {
+            let fallback = "the language server has no type hierarchy support; showing supertraits from the declaration and implementations below";
+            Some(note.map_or_else(
+                || fallback.to_string(),
+                |note| format!("{note}; {fallback}"),
+            ))
+        } else {
+            note
+        };
+
+        if items.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(with_note(
+                note.as_deref(),
+                "No type hierarchy found.",
+            ))]));
+        }
+
+        // Format hierarchy tree for each prepared item
+        let mut formatted = String::new();
+        for item in &items {
+            let _ = writeln!(
+                formatted,
+                "{}",
+                format_type_hierarchy_item(&self.paths, item)
+            );
+
+            formatted.push_str("\nSupertypes:\n");
+            self.expand_type_hierarchy(
+                item,
+                HierarchyDirection::Supertypes,
+                params.depth,
+                declared,
+                &mut formatted,
+            )
+            .await?;
+
+            formatted.push_str("\nSubtypes:\n");
+            self.expand_type_hierarchy(
+                item,
+                HierarchyDirection::Subtypes,
+                params.depth,
+                declared,
+                &mut formatted,
+            )
+            .await?;
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(with_note(
+            note.as_deref(),
+            formatted,
+        ))]))
+    }
+
+    /// Find all occurrences of a symbol in its file, classified as read or write.
+    #[tool(
+        description = "Find every occurrence of a symbol within one file, tagged as read, write or text. Shows where a local or field is mutated versus only read."
+    )]
+    async fn document_highlights(
+        &self,
+        Parameters(params): Parameters<DocumentHighlightsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let ResolvedPosition {
+            file_path,
+            line,
+            column,
+            note,
+        } = self.resolve_position(&params.position).await?;
+
+        // Ensure the document is open
+        self.lsp_client.did_open(&file_path).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to open document: {e}"),
+                None,
+            )
+        })?;
+
+        // Call LSP client
+        let mut highlights = self
+            .lsp_client
+            .document_highlights(&file_path, line, column)
+            .await
+            .map_err(|e| {
+                McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("document_highlights failed: {e}"),
+                    None,
+                )
+            })?;
+
+        // Format highlights with their access kind
+        let formatted = format_document_highlights(&self.paths, &file_path, &mut highlights)?;
+
+        Ok(CallToolResult::success(vec![Content::text(with_note(
+            note.as_deref(),
+            formatted,
+        ))]))
+    }
+
+    /// Render a line range with inferred types and parameter names inlined.
+    #[tool(
+        description = "Render a range of lines with the language server's inlay hints (inferred types, parameter names, iterator chain types) inlined as /* */ comments. Makes type-inferred code readable."
+    )]
+    async fn inlay_hints(
+        &self,
+        Parameters(params): Parameters<InlayHintsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let file_path = self.input_path(&params.file_path)?;
+
+        if params.start_line == 0 || params.end_line < params.start_line {
+            return Err(McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!(
+                    "invalid line range {}-{}: lines are 1-indexed and start_line must not exceed end_line",
+                    params.start_line, params.end_line
+                ),
+                None,
+            ));
+        }
+
+        // Ensure the document is open
+        self.lsp_client.did_open(&file_path).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to open document: {e}"),
+                None,
+            )
+        })?;
+
+        // Call LSP client
+        let hints = self
+            .lsp_client
+            .inlay_hints(&file_path, params.start_line, params.end_line)
+            .await
+            .map_err(|e| {
+                McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("inlay_hints failed: {e}"),
+                    None,
+                )
+            })?;
+
+        let content = std::fs::read_to_string(&file_path).map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to read file: {e}"),
+                None,
+            )
+        })?;
+
+        // Render source with hints inlined
+        let mut formatted = format!(
+            "{}:{}-{} ({} inlay hints)\n",
+            self.paths.label(&file_path),
+            params.start_line,
+            params.end_line,
+            hints.len()
+        );
+        formatted.push_str(&render_inlay_hints(
+            &content,
+            params.start_line,
+            params.end_line,
+            &hints,
+        ));
 
-        // Format locations with context
-        let formatted = format_locations(locations.as_slice(), 2)?;
-
         Ok(CallToolResult::success(vec![Content::text(formatted)]))
     }
 
-    /// Get type information and documentation for a symbol.
+    /// List the completions available at a position.
+    #[tool(
+        description = "List completions at a position: methods, fields and items available after `value.` or `path::`, with kinds, signatures and the imports added on accept. Fastest way to learn a type's API."
+    )]
+    pub async fn completions(
+        &self,
+        Parameters(params): Parameters<CompletionsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let ResolvedPosition {
+            file_path,
+            line,
+            column,
+            note,
+        } = self.resolve_cursor(&params.position).await?;
+
+        // Ensure the document is open
+        self.lsp_client.did_open(&file_path).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to open document: {e}"),
+                None,
+            )
+        })?;
+
+        // Call LSP client
+        let mut items = self
+            .lsp_client
+            .completion(&file_path, line, column)
+            .await
+            .map_err(|e| {
+                McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("completions failed: {e}"),
+                    None,
+                )
+            })?;
+
+        if items.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(with_note(
+                note.as_deref(),
+                "No completions found.",
+            ))]));
+        }
+
+        // Order as the server ranks them, then limit results
+        let total = items.len();
+        items.sort_by(|a, b| {
+            let a_key = a.sort_text.as_deref().unwrap_or(&a.label);
+            let b_key = b.sort_text.as_deref().unwrap_or(&b.label);
+            a_key.cmp(b_key)
+        });
+        items.truncate(params.max_results as usize);
+
+        // Resolve lazily computed imports when the server supports it
+        let supports_resolve = self
+            .lsp_client
+            .capabilities()
+            .completion_provider
+            .as_ref()
+            .and_then(|options| options.resolve_provider)
+            .unwrap_or(false);
+
+        let mut formatted = format!("{} of {total} completions:\n", items.len());
+        for item in items {
+            let item = if supports_resolve && item.data.is_some() {
+                match self.lsp_client.resolve_completion_item(item.clone()).await {
+                    Ok(resolved) => resolved,
+                    Err(_) => item,
+                }
+            } else {
+                item
+            };
+            let _ = writeln!(formatted, "{}", format_completion_item(&item));
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(with_note(
+            note.as_deref(),
+            formatted,
+        ))]))
+    }
+
+    /// Show the recursive expansion of the macro call at a position.
     #[tool(
-        description = "Get type signature and docs. Quick way to understand what something is without navigating away."
+        description = "Expand the macro at a position (macro_rules!, derive or attribute macro) recursively and show the generated code. rust-analyzer only."
     )]
-    async fn hover(
+    async fn expand_macro(
         &self,
-        Parameters(params): Parameters<HoverParams>,
+        Parameters(params): Parameters<ExpandMacroParams>,
     ) -> Result<CallToolResult, McpError> {
-        let file_path = PathBuf::from(&params.position.file_path);
-        let line = params.position.line;
-        let column = params.position.column;
+        let ResolvedPosition {
+            file_path,
+            line,
+            column,
+            note,
+        } = self.resolve_position(&params.position).await?;
 
         // Ensure the document is open
         self.lsp_client.did_open(&file_path).await.map_err(|e| {
@@ -389,43 +4063,90 @@ impl KadabraRunes {
         })?;
 
         // Call LSP client
-        let hover_result = self
+        let expanded = self
             .lsp_client
-            .hover(&file_path, line, column)
+            .expand_macro(&file_path, line, column)
             .await
             .map_err(|e| {
                 McpError::new(
                     ErrorCode::INTERNAL_ERROR,
-                    format!("hover failed: {e}"),
+                    format!("expand_macro failed: {e}"),
                     None,
                 )
             })?;
 
-        // Format hover information
-        let formatted = match hover_result {
-            Some(hover) => {
-                let text = extract_hover_text(hover.contents);
-                if text.is_empty() {
-                    "No hover information available.".to_string()
-                } else {
-                    text
-                }
+        // Format expansion within the output budget
+        let formatted = match expanded {
+            Some(expanded) => format_macro_expansion(
+                &expanded.name,
+                &expanded.expansion,
+                params.max_chars as usize,
+            ),
+            None => "No macro call found at this position.".to_string(),
+        };
+
+        Ok(CallToolResult::success(vec![Content::text(with_note(
+            note.as_deref(),
+            formatted,
+        ))]))
+    }
+
+    /// List the runnables (binaries, tests, benches, doc-tests) defined in a file.
+    #[tool(
+        description = "List binaries, tests, test modules, benches and doc-tests defined in a file, each with a ready-to-run cargo command line. rust-analyzer only."
+    )]
+    async fn runnables(
+        &self,
+        Parameters(params): Parameters<RunnablesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let file_path = self.input_path(&params.file_path)?;
+
+        // Ensure the document is open
+        self.lsp_client.did_open(&file_path).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to open document: {e}"),
+                None,
+            )
+        })?;
+
+        // Call LSP client
+        let runnables = self.lsp_client.runnables(&file_path).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("runnables failed: {e}"),
+                None,
+            )
+        })?;
+
+        // Format runnables
+        let formatted = if runnables.is_empty() {
+            "No runnables found in this file.".to_string()
+        } else {
+            let mut formatted = format!("Found {} runnable(s):\n", runnables.len());
+            for runnable in &runnables {
+                let _ = writeln!(formatted, "{}", format_runnable(&self.paths, runnable));
             }
-            None => "No hover information available.".to_string(),
+            formatted
         };
 
         Ok(CallToolResult::success(vec![Content::text(formatted)]))
     }
 
-    /// List all symbols defined in a file.
+    /// Find the tests and doc-tests that exercise the item at a position.
     #[tool(
-        description = "List all symbols in a file. Get a structural overview: functions, types, constants, etc."
+        description = "Find the test functions and doc-tests that exercise the function or type at a position, with ready-to-run cargo test command lines. rust-analyzer only."
     )]
-    async fn document_symbols(
+    async fn related_tests(
         &self,
-        Parameters(params): Parameters<DocumentSymbolsParams>,
+        Parameters(params): Parameters<RelatedTestsParams>,
     ) -> Result<CallToolResult, McpError> {
-        let file_path = PathBuf::from(&params.file_path);
+        let ResolvedPosition {
+            file_path,
+            line,
+            column,
+            note,
+        } = self.resolve_position(&params.position).await?;
 
         // Ensure the document is open
         self.lsp_client.did_open(&file_path).await.map_err(|e| {
@@ -437,87 +4158,407 @@ impl KadabraRunes {
         })?;
 
         // Call LSP client
-        let response = self
+        let mut tests: Vec<Runnable> = self
             .lsp_client
-            .document_symbols(&file_path)
+            .related_tests(&file_path, line, column)
             .await
             .map_err(|e| {
                 McpError::new(
                     ErrorCode::INTERNAL_ERROR,
-                    format!("document_symbols failed: {e}"),
+                    format!("related_tests failed: {e}"),
                     None,
                 )
-            })?;
+            })?
+            .into_iter()
+            .map(|test| test.runnable)
+            .collect();
 
-        // Format symbols
-        let formatted = match response {
-            DocumentSymbolResponse::Flat(symbols) => {
-                if symbols.is_empty() {
-                    "No symbols found in document.".to_string()
-                } else {
-                    format_symbol_information(&symbols)
-                }
-            }
-            DocumentSymbolResponse::Nested(symbols) => {
-                if symbols.is_empty() {
-                    "No symbols found in document.".to_string()
-                } else {
-                    format_document_symbols(&symbols, 0)
-                }
+        // relatedTests does not report the item's own doc-tests, so add the
+        // doc-test runnables whose range covers the position
+        let position = to_lsp_position(line, column).map_err(|e| {
+            McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!("invalid position: {e}"),
+                None,
+            )
+        })?;
+        let runnables = self.lsp_client.runnables(&file_path).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("runnables failed: {e}"),
+                None,
+            )
+        })?;
+        tests.extend(runnables.into_iter().filter(|runnable| {
+            runnable.label.starts_with("doctest")
+                && runnable.location.as_ref().is_some_and(|location| {
+                    location.target_range.start <= position && position <= location.target_range.end
+                })
+        }));
+
+        // Format tests
+        let formatted = if tests.is_empty() {
+            "No related tests found.".to_string()
+        } else {
+            let mut formatted = format!("Found {} related test(s):\n", tests.len());
+            for test in &tests {
+                let _ = writeln!(formatted, "{}", format_runnable(&self.paths, test));
             }
+            formatted
         };
 
+        Ok(CallToolResult::success(vec![Content::text(with_note(
+            note.as_deref(),
+            formatted,
+        ))]))
+    }
+
+    /// Show how a file is reached from its crate root and which submodules it declares.
+    #[tool(
+        description = "Show the module path of a file (e.g. crate::lsp::types), the chain of mod declarations from its crate root, and its submodules with file paths. rust-analyzer only."
+    )]
+    pub async fn module_tree(
+        &self,
+        Parameters(params): Parameters<ModuleTreeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let file_path = self.input_path(&params.file_path)?;
+
+        // Ensure the document is open
+        self.lsp_client.did_open(&file_path).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to open document: {e}"),
+                None,
+            )
+        })?;
+
+        // Query at the end of the file, which belongs to the file's own module
+        let content = std::fs::read_to_string(&file_path).map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to read file: {e}"),
+                None,
+            )
+        })?;
+        let (line, column) = end_of_file_position(&content);
+
+        // Walk up to the crate root
+        let ancestors = self.module_ancestors(&file_path, line, column).await?;
+        let crate_root = ancestors
+            .first()
+            .and_then(|declaration| declaration.uri.to_file_path().ok())
+            .unwrap_or_else(|| file_path.clone());
+
+        let mut module_path = String::from("crate");
+        for declaration in &ancestors {
+            let name = read_module_declaration(declaration)
+                .map_or_else(|| "<unknown>".to_string(), |(name, _)| name);
+            let _ = write!(module_path, "::{name}");
+        }
+
+        // Format the chain from the crate root down to this file
+        let mut formatted = format!(
+            "Module path: {module_path}\nCrate root: {}\n\ncrate - {}\n",
+            self.paths.label(&crate_root),
+            self.paths.label(&crate_root)
+        );
+        for (level, declaration) in ancestors.iter().enumerate() {
+            // Each module's contents live in the file declaring the next module down
+            let module_file = ancestors
+                .get(level + 1)
+                .and_then(|next| next.uri.to_file_path().ok())
+                .unwrap_or_else(|| file_path.clone());
+            let _ = writeln!(
+                formatted,
+                "{}{}",
+                "  ".repeat(level + 1),
+                format_module_line(&self.paths, declaration, &module_file)
+            );
+        }
+        if let Some(last) = formatted.strip_suffix('\n') {
+            formatted = format!("{last}  <- this file\n");
+        }
+
+        // Format the submodules below this file
+        self.expand_module_tree(
+            &file_path,
+            line,
+            column,
+            ancestors.len(),
+            params.depth,
+            &mut formatted,
+        )
+        .await?;
+
         Ok(CallToolResult::success(vec![Content::text(formatted)]))
     }
 
-    /// Search for symbols across the entire workspace.
+    /// Preview a structural search and replace rule as matches and a diff.
     #[tool(
-        description = "Search symbols by name across the workspace. Find types, functions, or modules without knowing their location."
+        description = "Preview a structural search and replace (SSR) rule such as `foo($a, $b) ==>> bar($b, $a)`. Matches on syntax and resolved paths, not text. Returns each match with context and the resulting edit as a unified diff; nothing is written to disk. rust-analyzer only."
     )]
-    async fn workspace_symbols(
+    async fn ssr_preview(
         &self,
-        Parameters(params): Parameters<WorkspaceSymbolsParams>,
+        Parameters(params): Parameters<SsrPreviewParams>,
     ) -> Result<CallToolResult, McpError> {
+        let file_path = self.input_path(&params.file_path)?;
+
+        // Ensure the document is open
+        self.lsp_client.did_open(&file_path).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to open document: {e}"),
+                None,
+            )
+        })?;
+
+        // Resolve paths in the module scope of the file, i.e. at its end
+        let content = std::fs::read_to_string(&file_path).map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to read file: {e}"),
+                None,
+            )
+        })?;
+        let (line, column) = end_of_file_position(&content);
+        let selections = match params.scope {
+            SsrScope::Workspace => Vec::new(),
+            SsrScope::File => {
+                let end = to_lsp_position(line, column)
+                    .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+                vec![Range::new(Position::new(0, 0), end)]
+            }
+        };
+
         // Call LSP client
-        let symbols = self
+        let edit = self
             .lsp_client
-            .workspace_symbols(&params.query)
+            .ssr(&params.rule, &file_path, line, column, selections)
             .await
             .map_err(|e| {
                 McpError::new(
                     ErrorCode::INTERNAL_ERROR,
-                    format!("workspace_symbols failed: {e}"),
+                    format!("ssr_preview failed: {e}"),
                     None,
                 )
             })?;
-
-        // Limit results if specified
-        let limited_symbols: Vec<_> = symbols
+        let files: Vec<_> = workspace_edit_text_edits(edit)
             .into_iter()
-            .take(params.max_results as usize)
+            .filter(|(_, edits)| !edits.is_empty())
             .collect();
 
-        // Format symbols
-        let formatted = if limited_symbols.is_empty() {
-            format!("No symbols found matching '{}'.", params.query)
-        } else {
-            format_symbol_information(&limited_symbols)
+        if files.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No matches for rule `{}`.",
+                params.rule
+            ))]));
+        }
+
+        // Format matches with context, then the edit as a diff
+        let match_count: usize = files.iter().map(|(_, edits)| edits.len()).sum();
+        let mut matches = format!(
+            "{match_count} match(es) in {} file(s) for rule `{}`\n\nMatches:\n",
+            files.len(),
+            params.rule
+        );
+        let mut sources = SourceFiles::new(&self.paths);
+        sources.insert(file_path, content);
+        let mut diff = String::new();
+        for (uri, edits) in &files {
+            let path = uri
+                .to_file_path()
+                .map_err(|()| McpError::new(ErrorCode::INTERNAL_ERROR, "invalid file URI", None))?;
+
+            for edit in edits {
+                let location = sources.location(uri, edit.range.start, SSR_CONTEXT_LINES);
+                let _ = writeln!(matches, "{}", format_location(&location));
+            }
+
+            let label = self.paths.label(&path);
+            diff.push_str(&format_edit_diff(&label, sources.content(&path), edits));
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(
+            format_ssr_preview(&matches, &diff, params.max_chars as usize),
+        )]))
+    }
+
+    /// Reload the workspace so changes to `Cargo.toml` files take effect.
+    #[tool(
+        description = "Reload the workspace after Cargo.toml changes (added dependencies, new workspace members, changed features) so later results are correct. rust-analyzer only."
+    )]
+    async fn reload_workspace(&self) -> Result<CallToolResult, McpError> {
+        // Call LSP client
+        self.lsp_client.reload_workspace().await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("reload_workspace failed: {e}"),
+                None,
+            )
+        })?;
+
+        Ok(CallToolResult::success(vec![Content::text(
+            "Workspace reload requested. rust-analyzer re-indexes in the background, so results may be incomplete for a moment.",
+        )]))
+    }
+
+    /// Re-run build scripts and rebuild proc macros.
+    #[tool(
+        description = "Re-run build scripts and rebuild proc macros after they changed, e.g. when generated code or derive output looks stale. rust-analyzer only."
+    )]
+    async fn rebuild_proc_macros(&self) -> Result<CallToolResult, McpError> {
+        // Call LSP client
+        self.lsp_client.rebuild_proc_macros().await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("rebuild_proc_macros failed: {e}"),
+                None,
+            )
+        })?;
+
+        Ok(CallToolResult::success(vec![Content::text(
+            "Proc macro rebuild requested. Build scripts and proc macros are rebuilt in the background.",
+        )]))
+    }
+
+    /// Report rust-analyzer's internal state.
+    #[tool(
+        description = "Report rust-analyzer's internal state: loaded workspaces, crates of a file, version and configuration. Useful when results look wrong or missing. rust-analyzer only."
+    )]
+    async fn analyzer_status(
+        &self,
+        Parameters(params): Parameters<AnalyzerStatusParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let file_path = params
+            .file_path
+            .as_deref()
+            .map(|path| self.input_path(path))
+            .transpose()?;
+
+        // Call LSP client
+        let status = self
+            .lsp_client
+            .analyzer_status(file_path.as_deref())
+            .await
+            .map_err(|e| {
+                McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("analyzer_status failed: {e}"),
+                    None,
+                )
+            })?;
+
+        Ok(CallToolResult::success(vec![Content::text(
+            truncate_output(&status, params.max_chars as usize),
+        )]))
+    }
+
+    /// Report rust-analyzer's memory usage.
+    #[tool(
+        description = "Report rust-analyzer's memory usage per query. Requires a rust-analyzer build with memory profiling enabled. rust-analyzer only."
+    )]
+    async fn memory_usage(&self) -> Result<CallToolResult, McpError> {
+        // Call LSP client
+        let usage = self.lsp_client.memory_usage().await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("memory_usage failed: {e}"),
+                None,
+            )
+        })?;
+
+        Ok(CallToolResult::success(vec![Content::text(usage)]))
+    }
+
+    /// Export the crate dependency graph.
+    #[tool(
+        description = "Export the crate dependency graph as Graphviz DOT, Mermaid or JSON. By default only workspace members are shown; set `full` to include std and registry dependencies. rust-analyzer only."
+    )]
+    async fn crate_graph(
+        &self,
+        Parameters(params): Parameters<CrateGraphParams>,
+    ) -> Result<CallToolResult, McpError> {
+        // Call LSP client
+        let dot = self
+            .lsp_client
+            .view_crate_graph(params.full)
+            .await
+            .map_err(|e| {
+                McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("crate_graph failed: {e}"),
+                    None,
+                )
+            })?;
+
+        // Format graph in the requested format
+        let formatted = match params.format {
+            GraphFormat::Dot => dot,
+            GraphFormat::Mermaid => format_crate_graph_mermaid(&parse_crate_graph_dot(&dot)),
+            GraphFormat::Json => serde_json::to_string_pretty(&parse_crate_graph_dot(&dot))
+                .map_err(|e| {
+                    McpError::new(
+                        ErrorCode::INTERNAL_ERROR,
+                        format!("failed to serialize crate graph: {e}"),
+                        None,
+                    )
+                })?,
         };
 
+        Ok(CallToolResult::success(vec![Content::text(
+            truncate_output(&formatted, params.max_chars as usize),
+        )]))
+    }
+
+    /// List the dependencies of the workspace.
+    #[tool(
+        description = "List every dependency of the workspace, including std and other sysroot crates, with its version and on-disk source root. rust-analyzer only."
+    )]
+    async fn dependencies(&self) -> Result<CallToolResult, McpError> {
+        // Call LSP client
+        let mut crates = self.lsp_client.fetch_dependency_list().await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("dependencies failed: {e}"),
+                None,
+            )
+        })?;
+        crates.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+
+        // Format dependencies
+        if crates.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "No dependencies found.",
+            )]));
+        }
+        let mut formatted = format!("Found {} dependencies:\n", crates.len());
+        for krate in &crates {
+            let path = self.paths.label_uri(&krate.path);
+            let _ = writeln!(
+                formatted,
+                "{} {} - {path}",
+                krate.name.as_deref().unwrap_or("<unknown>"),
+                krate.version.as_deref().unwrap_or("<unknown>")
+            );
+        }
+
         Ok(CallToolResult::success(vec![Content::text(formatted)]))
     }
 
-    /// Find all functions that call the function at the given position.
+    /// Show the syntax tree of the item at a position.
     #[tool(
-        description = "Find callers of a function. Build upward call graphs, trace who depends on this code."
+        description = "Show the syntax tree of the innermost item (fn, struct, impl, ...) at a position, with node kinds, spans and token text. Useful for checking how code parses, e.g. inside macros. rust-analyzer only."
     )]
-    async fn incoming_calls(
+    async fn view_syntax_tree(
         &self,
-        Parameters(params): Parameters<IncomingCallsParams>,
+        Parameters(params): Parameters<ItemViewParams>,
     ) -> Result<CallToolResult, McpError> {
-        let file_path = PathBuf::from(&params.position.file_path);
-        let line = params.position.line;
-        let column = params.position.column;
+        let ResolvedPosition {
+            file_path,
+            line,
+            column,
+            note,
+        } = self.resolve_position(&params.position).await?;
 
         // Ensure the document is open
         self.lsp_client.did_open(&file_path).await.map_err(|e| {
@@ -529,60 +4570,102 @@ impl KadabraRunes {
         })?;
 
         // Call LSP client
-        let calls = self
+        let tree = self
             .lsp_client
-            .incoming_calls(&file_path, line, column)
+            .view_syntax_tree(&file_path)
             .await
             .map_err(|e| {
                 McpError::new(
                     ErrorCode::INTERNAL_ERROR,
-                    format!("incoming_calls failed: {e}"),
+                    format!("view_syntax_tree failed: {e}"),
                     None,
                 )
             })?;
 
-        // Format call hierarchy
+        // Older servers return a text dump of the whole file rather than JSON
+        let Ok(root) = serde_json::from_str::<SyntaxTreeNode>(&tree) else {
+            return Ok(CallToolResult::success(vec![Content::text(with_note(
+                note.as_deref(),
+                truncate_output(&tree, params.max_chars as usize),
+            ))]));
+        };
+        let content = std::fs::read_to_string(&file_path).map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to read file: {e}"),
+                None,
+            )
+        })?;
+
+        // Format the innermost item at the position, or the whole file outside items
+        let (line, column) = (line.saturating_sub(1), column.saturating_sub(1));
+        let node = find_enclosing_item(&root, line, column).unwrap_or(&root);
         let mut formatted = String::new();
-        if calls.is_empty() {
-            formatted = "No incoming calls found.".to_string();
-        } else {
-            for call in calls {
-                let caller_name = &call.from.name;
-                let kind = symbol_kind_to_string(call.from.kind);
-                let file_path = call
-                    .from
-                    .uri
-                    .to_file_path()
-                    .map_or_else(|()| call.from.uri.to_string(), |p| p.display().to_string());
-                let (line, _) = from_lsp_position(call.from.selection_range.start);
-
-                let _ = writeln!(formatted, "\n[{kind}] {caller_name} - {file_path}:{line}");
-
-                // List call sites
-                for range in &call.from_ranges {
-                    let (call_line, call_col) = from_lsp_position(range.start);
-                    let _ = writeln!(
-                        formatted,
-                        "  Call site: line {call_line}, column {call_col}"
-                    );
-                }
-            }
-        }
+        render_syntax_tree(node, &content, 0, &mut formatted);
+
+        Ok(CallToolResult::success(vec![Content::text(with_note(
+            note.as_deref(),
+            truncate_output(&formatted, params.max_chars as usize),
+        ))]))
+    }
+
+    /// Show the HIR of the function at a position.
+    #[tool(
+        description = "Show the HIR (desugared high-level IR) of the function at a position, as rust-analyzer lowers it. Useful for checking desugaring of loops, `?` and macros. rust-analyzer only."
+    )]
+    async fn view_hir(
+        &self,
+        Parameters(params): Parameters<ItemViewParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let ResolvedPosition {
+            file_path,
+            line,
+            column,
+            note,
+        } = self.resolve_position(&params.position).await?;
+
+        // Ensure the document is open
+        self.lsp_client.did_open(&file_path).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("failed to open document: {e}"),
+                None,
+            )
+        })?;
+
+        // Call LSP client
+        let view = self
+            .lsp_client
+            .view_hir(&file_path, line, column)
+            .await
+            .map_err(|e| {
+                McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("view_hir failed: {e}"),
+                    None,
+                )
+            })?;
 
-        Ok(CallToolResult::success(vec![Content::text(formatted)]))
+        Ok(CallToolResult::success(vec![Content::text(with_note(
+            note.as_deref(),
+            truncate_output(&view, params.max_chars as usize),
+        ))]))
     }
 
-    /// Find all functions called by the function at the given position.
+    /// Show the MIR of the function at a position.
     #[tool(
-        description = "Find callees of a function. Build downward call graphs, trace execution flow."
+        description = "Show the MIR of the function at a position, as rust-analyzer lowers it. Useful when debugging borrows, moves and coercions. rust-analyzer only."
     )]
-    async fn outgoing_calls(
+    async fn view_mir(
         &self,
-        Parameters(params): Parameters<OutgoingCallsParams>,
+        Parameters(params): Parameters<ItemViewParams>,
     ) -> Result<CallToolResult, McpError> {
-        let file_path = PathBuf::from(&params.position.file_path);
-        let line = params.position.line;
-        let column = params.position.column;
+        let ResolvedPosition {
+            file_path,
+            line,
+            column,
+            note,
+        } = self.resolve_position(&params.position).await?;
 
         // Ensure the document is open
         self.lsp_client.did_open(&file_path).await.map_err(|e| {
@@ -594,69 +4677,38 @@ impl KadabraRunes {
         })?;
 
         // Call LSP client
-        let calls = self
+        let view = self
             .lsp_client
-            .outgoing_calls(&file_path, line, column)
+            .view_mir(&file_path, line, column)
             .await
             .map_err(|e| {
                 McpError::new(
                     ErrorCode::INTERNAL_ERROR,
-                    format!("outgoing_calls failed: {e}"),
+                    format!("view_mir failed: {e}"),
                     None,
                 )
             })?;
 
-        // Format call hierarchy
-        let mut formatted = String::new();
-        if calls.is_empty() {
-            formatted = "No outgoing calls found.".to_string();
-        } else {
-            for call in calls {
-                let callee_name = &call.to.name;
-                let kind = symbol_kind_to_string(call.to.kind);
-                let file_path = call
-                    .to
-                    .uri
-                    .to_file_path()
-                    .map_or_else(|()| call.to.uri.to_string(), |p| p.display().to_string());
-                let (line, _) = from_lsp_position(call.to.selection_range.start);
-                let _ = writeln!(formatted, "\n[{kind}] {callee_name} - {file_path}:{line}");
-
-                // List call sites
-                for range in &call.from_ranges {
-                    let (call_line, call_col) = from_lsp_position(range.start);
-                    let _ = writeln!(
-                        formatted,
-                        "  Call site: line {call_line}, column {call_col}"
-                    );
-                }
-            }
-        }
-
-        Ok(CallToolResult::success(vec![Content::text(formatted)]))
+        Ok(CallToolResult::success(vec![Content::text(with_note(
+            note.as_deref(),
+            truncate_output(&view, params.max_chars as usize),
+        ))]))
     }
 
-    /// Find all implementations of a trait or interface.
+    /// Resolve the docs.rs and local `cargo doc` URLs of the item at a position.
     #[tool(
-        description = "Find trait/interface implementations. Discover concrete types, understand polymorphism."
+        description = "Resolve the documentation of the item at a position: the docs.rs (or doc.rust-lang.org) URL and, when `target/doc` was built with `cargo doc`, the local HTML file to read the rendered API docs offline. rust-analyzer only."
     )]
-    async fn implementations(
+    async fn external_docs(
         &self,
-        Parameters(params): Parameters<ImplementationsParams>,
+        Parameters(params): Parameters<ExternalDocsParams>,
     ) -> Result<CallToolResult, McpError> {
-        // Extract position from params
-        let (file_path, line, column) = match &params.query {
-            SymbolQuery::Position(pos) => (PathBuf::from(&pos.file_path), pos.line, pos.column),
-            SymbolQuery::Name(SymbolNameParams { symbol, .. }) => {
-                return Err(McpError::new(
-                    ErrorCode::INVALID_PARAMS,
-                    format!(
-                        "Symbol name queries not yet implemented. Use workspace_symbols to find '{symbol}' first, then use position-based query."
-                    ),
-                    None,
-                ));
-            }
-        };
+        let ResolvedPosition {
+            file_path,
+            line,
+            column,
+            note,
+        } = self.resolve_position(&params.position).await?;
 
         // Ensure the document is open
         self.lsp_client.did_open(&file_path).await.map_err(|e| {
@@ -668,68 +4720,175 @@ impl KadabraRunes {
         })?;
 
         // Call LSP client
-        let response = self
+        let docs = self
             .lsp_client
-            .implementations(&file_path, line, column)
+            .external_docs(&file_path, line, column)
             .await
             .map_err(|e| {
                 McpError::new(
                     ErrorCode::INTERNAL_ERROR,
-                    format!("implementations failed: {e}"),
+                    format!("external_docs failed: {e}"),
                     None,
                 )
             })?;
 
-        // Convert response to locations
-        let locations = goto_response_to_locations(response);
-
-        // Format locations with context
-        let formatted = format_locations(locations.as_slice(), 2)?;
-
-        Ok(CallToolResult::success(vec![Content::text(formatted)]))
+        Ok(CallToolResult::success(vec![Content::text(with_note(
+            note.as_deref(),
+            format_external_docs(&self.paths, &docs),
+        ))]))
     }
 
-    /// Jump to the type definition of a symbol.
+    /// Get the errors and warnings the language server reports for a file or the open files.
     #[tool(
-        description = "Jump to a symbol's type definition. Understand what type a variable or expression has."
+        description = "Get the errors and warnings the language server currently reports, for one file or for all open files, grouped by file and enclosing symbol. Works with servers that push diagnostics and servers that are pulled. Does not run cargo check; use check_workspace for that."
     )]
-    async fn type_definition(
+    pub async fn diagnostics(
         &self,
-        Parameters(params): Parameters<TypeDefinitionParams>,
+        Parameters(params): Parameters<DiagnosticsParams>,
     ) -> Result<CallToolResult, McpError> {
-        let file_path = PathBuf::from(&params.position.file_path);
-        let line = params.position.line;
-        let column = params.position.column;
+        let file_path = params
+            .file_path
+            .as_deref()
+            .map(|path| self.input_path(path))
+            .transpose()?;
 
-        // Ensure the document is open
-        self.lsp_client.did_open(&file_path).await.map_err(|e| {
+        // Call LSP client
+        let diagnostics = match &file_path {
+            Some(file_path) => {
+                // Ensure the document is open
+                self.lsp_client.did_open(file_path).await.map_err(|e| {
+                    McpError::new(
+                        ErrorCode::INTERNAL_ERROR,
+                        format!("failed to open document: {e}"),
+                        None,
+                    )
+                })?;
+                self.lsp_client
+                    .diagnostics(file_path)
+                    .await
+                    .map(|diagnostics| HashMap::from([(file_path.clone(), diagnostics)]))
+            }
+            None => self.lsp_client.all_diagnostics().await.map(|all| {
+                all.into_iter()
+                    .filter_map(|(uri, diagnostics)| Some((uri.to_file_path().ok()?, diagnostics)))
+                    .collect()
+            }),
+        }
+        .map_err(|e| {
             McpError::new(
                 ErrorCode::INTERNAL_ERROR,
-                format!("failed to open document: {e}"),
+                format!("diagnostics failed: {e}"),
                 None,
             )
         })?;
 
-        // Call LSP client
-        let response = self
-            .lsp_client
-            .type_definition(&file_path, line, column)
+        let mut files: Vec<(PathBuf, Vec<Diagnostic>)> = diagnostics
+            .into_iter()
+            .filter(|(_, diagnostics)| !diagnostics.is_empty())
+            .collect();
+        files.sort_by(|(a, _), (b, _)| a.cmp(b));
+        if files.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "No diagnostics reported.",
+            )]));
+        }
+
+        let count = |severity| {
+            files
+                .iter()
+                .flat_map(|(_, diagnostics)| diagnostics)
+                .filter(|d| d.severity == Some(severity))
+                .count()
+        };
+        let mut formatted = format!(
+            "{} error(s), {} warning(s) in {} file(s)\n",
+            count(DiagnosticSeverity::ERROR),
+            count(DiagnosticSeverity::WARNING),
+            files.len()
+        );
+        formatted.push_str(&self.format_grouped_diagnostics(files).await);
+
+        Ok(CallToolResult::success(vec![Content::text(
+            truncate_output(&formatted, params.max_chars as usize),
+        )]))
+    }
+
+    /// Run `cargo check` (or clippy) through rust-analyzer and collect the diagnostics.
+    #[tool(
+        description = "Run cargo check (or clippy, as configured in rust-analyzer) on the workspace, wait for it to finish, and return compiler errors and warnings grouped by file and enclosing symbol. rust-analyzer only."
+    )]
+    pub async fn check_workspace(
+        &self,
+        Parameters(params): Parameters<CheckWorkspaceParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let file_path = params
+            .file_path
+            .as_deref()
+            .map(|path| self.input_path(path))
+            .transpose()?;
+        let timeout = std::time::Duration::from_secs(params.timeout_secs);
+
+        // Call LSP client and wait for flycheck to finish
+        self.lsp_client
+            .run_flycheck(file_path.as_deref(), timeout)
             .await
             .map_err(|e| {
                 McpError::new(
                     ErrorCode::INTERNAL_ERROR,
-                    format!("type_definition failed: {e}"),
+                    format!("check_workspace failed: {e}"),
                     None,
                 )
             })?;
 
-        // Convert response to locations
-        let locations = goto_response_to_locations(response);
+        let diagnostics = self.lsp_client.all_diagnostics().await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("check_workspace failed: {e}"),
+                None,
+            )
+        })?;
+
+        // Keep compiler diagnostics; rustc repeats related notes as separate hints
+        let mut files: Vec<(PathBuf, Vec<Diagnostic>)> = diagnostics
+            .into_iter()
+            .filter_map(|(uri, diagnostics)| {
+                let diagnostics: Vec<_> = diagnostics
+                    .into_iter()
+                    .filter(|d| d.source.as_deref() != Some("rust-analyzer"))
+                    .filter(|d| d.severity != Some(DiagnosticSeverity::HINT))
+                    .collect();
+                let path = uri.to_file_path().ok()?;
+                (!diagnostics.is_empty()).then_some((path, diagnostics))
+            })
+            .collect();
+        files.sort_by(|(a, _), (b, _)| a.cmp(b));
 
-        // Format locations with context
-        let formatted = format_locations(locations.as_slice(), 2)?;
+        let count = |severity| {
+            files
+                .iter()
+                .flat_map(|(_, diagnostics)| diagnostics)
+                .filter(|d| d.severity == Some(severity))
+                .count()
+        };
+        let (errors, warnings) = (
+            count(DiagnosticSeverity::ERROR),
+            count(DiagnosticSeverity::WARNING),
+        );
+        if files.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "Check finished: no errors or warnings.",
+            )]));
+        }
 
-        Ok(CallToolResult::success(vec![Content::text(formatted)]))
+        let mut formatted = format!(
+            "Check finished: {errors} error(s), {warnings} warning(s) in {} file(s)\n",
+            files.len()
+        );
+        formatted.push_str(&self.format_grouped_diagnostics(files).await);
+
+        Ok(CallToolResult::success(vec![Content::text(
+            truncate_output(&formatted, params.max_chars as usize),
+        )]))
     }
 }
 
@@ -759,6 +4918,728 @@ impl ServerHandler for KadabraRunes {
 mod tests {
     // Tests require a real LSP client instance, which is complex to mock.
     // Integration tests will be added separately.
+    use super::*;
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_enclosing_symbol_and_format_diagnostic() {
+        let range =
+            |start: u32, end: u32| Range::new(Position::new(start, 0), Position::new(end, 0));
+        let symbol = |name: &str, kind, range, children| DocumentSymbol {
+            name: name.to_string(),
+            detail: None,
+            kind,
+            tags: None,
+            deprecated: None,
+            range,
+            selection_range: range,
+            children,
+        };
+        let symbols = DocumentSymbolResponse::Nested(vec![symbol(
+            "impl Adder",
+            lsp_types::SymbolKind::OBJECT,
+            range(0, 10),
+            Some(vec![symbol(
+                "calculate",
+                lsp_types::SymbolKind::METHOD,
+                range(2, 5),
+                None,
+            )]),
+        )]);
+
+        assert_eq!(
+            enclosing_symbol(&symbols, Position::new(3, 4)).as_deref(),
+            Some("[method] calculate")
+        );
+        assert_eq!(
+            enclosing_symbol(&symbols, Position::new(8, 0)).as_deref(),
+            Some("[object] impl Adder")
+        );
+        assert_eq!(enclosing_symbol(&symbols, Position::new(12, 0)), None);
+
+        let diagnostic = Diagnostic {
+            range: range(7, 7),
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: Some(NumberOrString::String("unused_variables".to_string())),
+            message: "unused variable: `x`\nnote: on by default".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            format_diagnostic(&diagnostic),
+            "warning[unused_variables] 8:1: unused variable: `x`\n      note: on by default"
+        );
+    }
+
+    #[test]
+    fn test_anchor_column() {
+        let text = "    let total = add(total, 1) + total;";
+
+        // Exact columns on identifiers are kept
+        assert_eq!(anchor_column(text, 7, 9, None, None), Ok((9, None)));
+
+        // Whitespace snaps to the nearest identifier, preferring the right on ties
+        let (column, note) = anchor_column(text, 7, 8, None, None).unwrap();
+        assert_eq!(column, 9);
+        assert_eq!(
+            note.as_deref(),
+            Some("column 8 on line 7 is not on an identifier; snapped to `total` at column 9")
+        );
+        // A comma right after `total` snaps back to it
+        assert_eq!(anchor_column(text, 7, 26, None, None).unwrap().0, 21);
+
+        // Tokens anchor by occurrence, by nearest column, or to the first with a note
+        assert_eq!(anchor_column(text, 7, 0, Some("add"), None), Ok((17, None)));
+        assert_eq!(
+            anchor_column(text, 7, 0, Some("total"), Some(2)),
+            Ok((21, None))
+        );
+        assert_eq!(
+            anchor_column(text, 7, 35, Some("total"), None).unwrap().0,
+            33
+        );
+        let (column, note) = anchor_column(text, 7, 0, Some("total"), None).unwrap();
+        assert_eq!(column, 9);
+        assert!(
+            note.unwrap()
+                .contains("occurs 3 times on line 7; picked the first")
+        );
+
+        // Identifiers match whole tokens; operators match as text
+        assert_eq!(anchor_column(text, 7, 0, Some("+"), None), Ok((31, None)));
+        assert_eq!(
+            anchor_column(text, 7, 0, Some("tot"), None),
+            Err("`tot` not found on line 7; identifiers on that line: let, total, add".to_string())
+        );
+        assert!(
+            anchor_column(text, 7, 0, Some("total"), Some(4))
+                .unwrap_err()
+                .contains("line 7 has 3")
+        );
+    }
+
+    #[test]
+    fn test_format_external_docs() {
+        let docs = ExternalDocsPair {
+            web: Some(Url::parse("https://docs.rs/demo/0.1.0/demo/fn.run.html").unwrap()),
+            local: Some(Url::parse("file:///nonexistent/target/doc/demo/fn.run.html").unwrap()),
+        };
+        assert_eq!(
+            format_external_docs(&PathLabels::new(PathBuf::from("/ws")), &docs),
+            "Web: https://docs.rs/demo/0.1.0/demo/fn.run.html\n\
+             Local: not built; run `cargo doc` to generate /nonexistent/target/doc/demo/fn.run.html\n"
+        );
+
+        assert_eq!(
+            format_external_docs(
+                &PathLabels::new(PathBuf::from("/ws")),
+                &ExternalDocsPair::default()
+            ),
+            "No documentation found for the item at this position."
+        );
+    }
+
+    #[test]
+    fn test_render_syntax_tree() {
+        let content = "fn a() {}\n";
+        let token = |kind: &str, start: u32, end: u32| SyntaxTreeNode {
+            node_type: "Token".to_string(),
+            kind: kind.to_string(),
+            start: [start, 0, start],
+            end: [end, 0, end],
+            children: Vec::new(),
+        };
+        let function = SyntaxTreeNode {
+            node_type: "Node".to_string(),
+            kind: "FN".to_string(),
+            start: [0, 0, 0],
+            end: [9, 0, 9],
+            children: vec![
+                token("FN_KW", 0, 2),
+                token("WHITESPACE", 2, 3),
+                token("IDENT", 3, 4),
+            ],
+        };
+        let root = SyntaxTreeNode {
+            node_type: "Node".to_string(),
+            kind: "SOURCE_FILE".to_string(),
+            start: [0, 0, 0],
+            end: [10, 1, 0],
+            children: vec![function],
+        };
+
+        let item = find_enclosing_item(&root, 0, 3).expect("should find the fn");
+        assert_eq!(item.kind, "FN");
+        assert!(find_enclosing_item(&root, 1, 0).is_none());
+
+        let mut out = String::new();
+        render_syntax_tree(item, content, 0, &mut out);
+        assert_eq!(
+            out,
+            "FN@1:1..1:10\n  FN_KW \"fn\"\n  WHITESPACE \" \"\n  IDENT \"a\"\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_crate_graph_dot() {
+        let dot = "digraph rust_analyzer_crate_graph {\n    _2[label=\"sample_project\"][shape=\"box\"];\n    _1[label=\"core\"][shape=\"box\"];\n    _2 -> _1[label=\"\"];\n    _2 -> _1[label=\"core2\"];\n}\n";
+
+        let graph = parse_crate_graph_dot(dot);
+
+        assert_eq!(
+            graph.crates,
+            vec![
+                CrateNode {
+                    id: "_1".to_string(),
+                    name: "core".to_string()
+                },
+                CrateNode {
+                    id: "_2".to_string(),
+                    name: "sample_project".to_string()
+                },
+            ]
+        );
+        assert_eq!(graph.dependencies.len(), 2);
+        assert_eq!(graph.dependencies[0].name, None);
+        assert_eq!(graph.dependencies[1].name.as_deref(), Some("core2"));
+        assert_eq!(
+            format_crate_graph_mermaid(&graph),
+            "graph LR\n    crate1[\"core\"]\n    crate2[\"sample_project\"]\n    crate2 --> crate1\n    crate2 -->|core2| crate1\n"
+        );
+    }
+
+    #[test]
+    fn test_format_edit_diff() {
+        let content = "fn main() {\n    let a = add(x, y);\n    let b = add(1, 2);\n}\n";
+        let edit = |line, start, end, text: &str| TextEdit {
+            range: Range::new(Position::new(line, start), Position::new(line, end)),
+            new_text: text.to_string(),
+        };
+        let edits = vec![
+            edit(2, 12, 21, "add(2, 1)"),
+            edit(1, 12, 21, "add(y,\n        x)"),
+        ];
+
+        assert_eq!(
+            format_edit_diff("src/main.rs", content, &edits),
+            "--- a/src/main.rs\n+++ b/src/main.rs\n\
+             @@ -2,1 +2,2 @@\n-    let a = add(x, y);\n+    let a = add(y,\n+        x);\n\
+             @@ -3,1 +4,1 @@\n-    let b = add(1, 2);\n+    let b = add(2, 1);\n"
+        );
+    }
+
+    #[test]
+    fn test_end_of_file_position() {
+        assert_eq!(end_of_file_position(""), (1, 1));
+        assert_eq!(end_of_file_position("mod a;\n"), (2, 1));
+        assert_eq!(end_of_file_position("mod a;\nfn b() {}"), (2, 10));
+    }
+
+    #[test]
+    fn test_truncate_output() {
+        assert_eq!(truncate_output("short", 10), "short");
+
+        let text = "line one\nline two\nline three\n";
+        assert_eq!(
+            truncate_output(text, 12),
+            "line one\n\n... truncated: showing 9 of 29 characters\n"
+        );
+
+        // A single long line is cut at the budget, on a character boundary
+        assert_eq!(
+            truncate_output("ééééé", 2),
+            "éé\n... truncated: showing 2 of 5 characters\n"
+        );
+    }
+
+    #[test]
+    fn test_read_context_lines_past_end_of_file() {
+        let file = tempfile::NamedTempFile::new().expect("temp file");
+        std::fs::write(file.path(), "fn a() {}\nfn b() {}\n").expect("write temp file");
+        assert_eq!(
+            read_context_lines(file.path(), 2, 0).unwrap(),
+            ">    2 | fn b() {}\n"
+        );
+        assert_eq!(read_context_lines(file.path(), 3, 0).unwrap(), "");
+        assert_eq!(read_context_lines(file.path(), 4, 1).unwrap(), "");
+        assert_eq!(
+            read_context_lines(file.path(), 3, 1).unwrap(),
+            "     2 | fn b() {}\n"
+        );
+    }
+
+    #[test]
+    fn test_supertrait_offsets() {
+        let bounds = |header: &str| -> Vec<String> {
+            supertrait_offsets(header)
+                .into_iter()
+                .map(|offset| {
+                    header[offset..]
+                        .split(|c: char| !c.is_alphanumeric() && c != '_')
+                        .next()
+                        .unwrap_or_default()
+                        .to_string()
+                })
+                .collect()
+        };
+        assert_eq!(
+            bounds("<T: Copy>: fmt::Display + Add<T, Output = T> + 'static + ?Sized {"),
+            ["Display", "Add"]
+        );
+        assert_eq!(bounds(": Fn(u8) -> u8\nwhere\n    Self: Sized,\n{"), ["Fn"]);
+        assert!(bounds(" where Self: Clone {").is_empty());
+        assert!(bounds(" {\n    fn f(&self) -> u8;\n}").is_empty());
+        assert_eq!(
+            offset_to_position("trait A:\n    B {}", 13),
+            Position::new(1, 4)
+        );
+    }
+
+    #[test]
+    fn test_format_ssr_preview() {
+        let matches = "1 match(es) in 1 file(s) for rule `a ==>> b`\n\nMatches:\nsrc/lib.rs:1:1\n";
+        let diff = "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1 @@\n-a\n+b\n";
+        assert_eq!(
+            format_ssr_preview(matches, diff, 1000),
+            format!("{matches}\nDiff:\n```diff\n{diff}```\n")
+        );
+
+        // Truncated inside the diff, the code block is still closed
+        let text = format_ssr_preview(matches, diff, matches.len() + 40);
+        assert!(text.contains("... truncated"), "got: {text}");
+        assert!(text.ends_with("characters\n```\n"), "got: {text}");
+
+        // Truncated before the diff, no code block is opened
+        let text = format_ssr_preview(matches, diff, 20);
+        assert!(!text.contains("```"), "got: {text}");
+    }
+
+    #[test]
+    fn test_format_macro_expansion() {
+        // rust-analyzer's expansions usually lack a trailing newline
+        assert_eq!(
+            format_macro_expansion("vec!", "{\n    1\n}", 100),
+            "Expansion of vec!:\n```rust\n{\n    1\n}\n```"
+        );
+        assert_eq!(
+            format_macro_expansion("vec!", "{}\n", 100),
+            "Expansion of vec!:\n```rust\n{}\n```"
+        );
+    }
+
+    #[test]
+    fn test_format_completion_item() {
+        let item = lsp_types::CompletionItem {
+            label: "type_id".to_string(),
+            kind: Some(lsp_types::CompletionItemKind::METHOD),
+            label_details: Some(lsp_types::CompletionItemLabelDetails {
+                detail: Some("(use std::any::Any)".to_string()),
+                description: Some("fn(&self) -> TypeId".to_string()),
+            }),
+            additional_text_edits: Some(vec![lsp_types::TextEdit {
+                range: lsp_types::Range::default(),
+                new_text: "use std::any::Any;\n".to_string(),
+            }]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            format_completion_item(&item),
+            "[method] type_id(use std::any::Any) - fn(&self) -> TypeId\n    on accept: use std::any::Any;"
+        );
+    }
+
+    #[test]
+    fn test_render_inlay_hints() {
+        let content = "fn main() {\n    let x = add(1, 2);\n}\n";
+        let hints = vec![
+            lsp_types::InlayHint {
+                position: lsp_types::Position::new(1, 9),
+                label: lsp_types::InlayHintLabel::String(": i32".to_string()),
+                kind: Some(lsp_types::InlayHintKind::TYPE),
+                text_edits: None,
+                tooltip: None,
+                padding_left: Some(false),
+                padding_right: Some(false),
+                data: None,
+            },
+            lsp_types::InlayHint {
+                position: lsp_types::Position::new(1, 16),
+                label: lsp_types::InlayHintLabel::LabelParts(vec![lsp_types::InlayHintLabelPart {
+                    value: "a:".to_string(),
+                    ..Default::default()
+                }]),
+                kind: Some(lsp_types::InlayHintKind::PARAMETER),
+                text_edits: None,
+                tooltip: None,
+                padding_left: Some(false),
+                padding_right: Some(true),
+                data: None,
+            },
+        ];
+
+        let rendered = render_inlay_hints(content, 2, 2, &hints);
+        assert_eq!(
+            rendered,
+            "     2 |     let x/*: i32*/ = add(/*a:*/ 1, 2);\n"
+        );
+    }
+
+    #[test]
+    fn test_format_location() {
+        let content = "fn main() {\n    let a = add(1, 2);\n}\n";
+        let location = location_in_content("src/main.rs".to_string(), content, 2, 13, 1);
+        assert_eq!(location.context, "    let a = add(1, 2);");
+        assert_eq!(
+            location.context_before,
+            Some(vec!["fn main() {".to_string()])
+        );
+        assert_eq!(location.context_after, Some(vec!["}".to_string()]));
+        assert_eq!(
+            format_location(&location),
+            "src/main.rs:2:13\n     1 | fn main() {\n>    2 |     let a = add(1, 2);\n     3 | }\n"
+        );
+
+        let location = location_in_content("src/main.rs".to_string(), content, 1, 4, 0);
+        assert_eq!(location.context_before, None);
+        assert_eq!(
+            format_location(&location),
+            "src/main.rs:1:4\n>    1 | fn main() {\n"
+        );
+
+        // Context is bounded, as a page always keeps its first location
+        assert_eq!(context_lines(LocationContext::Lines(3)), 3);
+        assert_eq!(context_lines(LocationContext::Lines(100_000)), 50);
+        assert_eq!(context_lines(LocationContext::Mode(ContextMode::None)), 0);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_enclosing_item() {
+        let content = "/// Handles a request.\npub fn handle(\n    id: u32,\n) -> u32 {\n    let a = id;\n\n    a + 1\n}\n";
+        let range = |start: (u32, u32), end: (u32, u32)| {
+            Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1))
+        };
+        let handle = DocumentSymbol {
+            name: "handle".to_string(),
+            detail: None,
+            kind: SymbolKind::FUNCTION,
+            tags: None,
+            deprecated: None,
+            range: range((0, 0), (7, 1)),
+            selection_range: range((1, 7), (1, 13)),
+            children: None,
+        };
+        let symbols = [handle];
+
+        let symbol = innermost_item(&symbols, Position::new(6, 4)).unwrap();
+        assert_eq!(symbol.name, "handle");
+        assert!(innermost_item(&symbols, Position::new(9, 0)).is_none());
+
+        let item = enclosing_item(content, symbol);
+        assert_eq!(item.name, "fn handle");
+        assert_eq!((item.start_line, item.end_line), (1, 8));
+        assert_eq!(item.signature_line, 2);
+        assert_eq!(
+            item.signature,
+            vec!["pub fn handle(", "    id: u32,", ") -> u32 {"]
+        );
+
+        let mut location = location_in_content("src/lib.rs".to_string(), content, 7, 5, 0);
+        location.enclosing = Some(item.clone());
+        assert_eq!(
+            format_location(&location),
+            "src/lib.rs:7:5\ninside `fn handle` (lines 1\u{2013}8)\n     2 | pub fn handle(\n     3 |     id: u32,\n     4 | ) -> u32 {\n         ...\n>    7 |     a + 1\n"
+        );
+
+        // A location within the signature is marked there
+        let mut location = location_in_content("src/lib.rs".to_string(), content, 3, 9, 0);
+        location.enclosing = Some(item);
+        assert_eq!(
+            format_location(&location),
+            "src/lib.rs:3:9\ninside `fn handle` (lines 1\u{2013}8)\n     2 | pub fn handle(\n>    3 |     id: u32,\n     4 | ) -> u32 {\n"
+        );
+
+        // Without source lines only the header remains
+        let mut location = location_in_content("src/lib.rs".to_string(), content, 7, 5, 0);
+        location.context.clear();
+        assert_eq!(format_location(&location), "src/lib.rs:7:5\n");
+    }
+
+    #[test]
+    fn test_paginate() {
+        let items = vec![
+            ("a.rs", 1),
+            ("b.rs", 2),
+            ("a.rs", 3),
+            ("c.rs", 4),
+            ("a.rs", 5),
+        ];
+        let page = PageParams {
+            limit: Some(3),
+            offset: 1,
+            ..PageParams::default()
+        };
+        let (mut page_items, mut info) =
+            paginate(items.clone(), &page, DEFAULT_PAGE_LIMIT, |item| {
+                item.0.to_string()
+            })
+            .unwrap();
+        assert_eq!(page_items, vec![("b.rs", 2), ("a.rs", 3), ("c.rs", 4)]);
+        assert_eq!((info.total, info.offset, info.returned), (5, 1, 3));
+        assert_eq!(info.per_file[0].file_path, "a.rs");
+        assert_eq!(info.per_file[0].count, 3);
+        assert_eq!(info.next_cursor.as_deref(), Some("offset:4"));
+
+        // Items beyond the character budget move to the next page
+        let rendered = render_page(&mut page_items, &mut info, 14, |item| {
+            format!("{}:{}\n", item.0, item.1)
+        });
+        assert_eq!(rendered, vec!["b.rs:2\n", "a.rs:3\n"]);
+        assert_eq!(info.next_cursor.as_deref(), Some("offset:3"));
+        assert_eq!(
+            format_page(&info, &rendered, ""),
+            "Showing results 2-3 of 5 in 3 file(s):\n  a.rs: 3\n  b.rs: 1\n  c.rs: 1\nNext page: cursor \"offset:3\"\n\nb.rs:2\na.rs:3\n"
+        );
+
+        let page = PageParams {
+            cursor: Some("offset:3".to_string()),
+            ..PageParams::default()
+        };
+        let (page_items, info) = paginate(items.clone(), &page, DEFAULT_PAGE_LIMIT, |item| {
+            item.0.to_string()
+        })
+        .unwrap();
+        assert_eq!(page_items, vec![("c.rs", 4), ("a.rs", 5)]);
+        assert_eq!(info.next_cursor, None);
+
+        let page = PageParams {
+            limit: Some(0),
+            ..PageParams::default()
+        };
+        let error = paginate(items.clone(), &page, DEFAULT_PAGE_LIMIT, |item| {
+            item.0.to_string()
+        })
+        .unwrap_err();
+        assert_eq!(error.code, ErrorCode::INVALID_PARAMS);
+
+        // A complete first page has no summary
+        let (_, info) = paginate(items.clone(), &PageParams::default(), 10, |item| {
+            item.0.to_string()
+        })
+        .unwrap();
+        assert_eq!(format_page(&info, &["x".to_string()], ""), "x");
+
+        let page = PageParams {
+            cursor: Some("page 2".to_string()),
+            ..PageParams::default()
+        };
+        assert!(paginate(items, &page, 10, |item| item.0.to_string()).is_err());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_is_test_code() {
+        let content = "mod tests {\n    #[test]\n    fn it_runs() {\n        run();\n    }\n}\n";
+        let range =
+            |start: u32, end: u32| Range::new(Position::new(start, 0), Position::new(end, 0));
+        let symbol = |name: &str, kind, range, selection, children| DocumentSymbol {
+            name: name.to_string(),
+            detail: None,
+            kind,
+            tags: None,
+            deprecated: None,
+            range,
+            selection_range: selection,
+            children,
+        };
+        let it_runs = symbol(
+            "it_runs",
+            SymbolKind::FUNCTION,
+            range(1, 4),
+            range(2, 2),
+            None,
+        );
+        assert!(is_test_code(
+            Path::new("/ws/src/lib.rs"),
+            &[&it_runs],
+            content
+        ));
+        let helper = symbol(
+            "helper",
+            SymbolKind::FUNCTION,
+            range(2, 4),
+            range(2, 2),
+            None,
+        );
+        assert!(!is_test_code(
+            Path::new("/ws/src/lib.rs"),
+            &[&helper],
+            content
+        ));
+        assert!(is_test_code(Path::new("/ws/tests/it.rs"), &[], ""));
+        let tests = symbol("tests", SymbolKind::MODULE, range(0, 5), range(0, 0), None);
+        assert!(is_test_code(Path::new("/ws/src/lib.rs"), &[&tests], ""));
+        let checks = symbol("checks", SymbolKind::MODULE, range(0, 3), range(1, 1), None);
+        let content = "#[cfg(test)]\nmod checks {\n}\n";
+        assert!(is_test_code(
+            Path::new("/ws/src/lib.rs"),
+            &[&checks],
+            content
+        ));
+        let content = "#[cfg(not(test))]\nmod checks {\n}\n";
+        assert!(!is_test_code(
+            Path::new("/ws/src/lib.rs"),
+            &[&checks],
+            content
+        ));
+        let content = "\nmod checks {\n}\n";
+        assert!(!is_test_code(
+            Path::new("/ws/src/lib.rs"),
+            &[&checks],
+            content
+        ));
+        // The body of an out-of-line `#[cfg(test)] mod tests;`
+        assert!(is_test_code(Path::new("/ws/src/foo/tests.rs"), &[], ""));
+    }
+
+    #[test]
+    fn test_is_test_attribute() {
+        for attribute in [
+            "#[test]",
+            "#[tokio::test]",
+            "#[tokio::test(flavor = \"multi_thread\")]",
+            "#[cfg(test)]",
+            "#[cfg(all(test, feature = \"slow\"))]",
+            "#[cfg(any(test, all(test, unix)))]",
+        ] {
+            assert!(is_test_attribute(attribute), "{attribute}");
+        }
+        for attribute in [
+            "#[cfg(not(test))]",
+            "#[cfg(any(test, unix))]",
+            "#[cfg(feature = \"test-utils\")]",
+            "#[doc(alias = \"test\")]",
+            "#[attested]",
+            "// #[test]",
+        ] {
+            assert!(!is_test_attribute(attribute), "{attribute}");
+        }
+    }
+
+    #[test]
+    fn test_group_references() {
+        let range =
+            |start: u32, end: u32| Range::new(Position::new(start, 0), Position::new(end, 0));
+        let reference = |file_path: &str, item: &str, in_tests| GroupedReference {
+            location: lsp_types::Location::new(
+                Url::parse(&format!("file://{file_path}")).unwrap(),
+                range(0, 0),
+            ),
+            file_path: file_path.to_string(),
+            item: item.to_string(),
+            in_tests,
+        };
+        let references = [
+            reference("/ws/src/lib.rs", "impl Server > fn handle", false),
+            reference("/ws/src/lib.rs", "impl Server > fn handle", false),
+            reference("/ws/src/lib.rs", TOP_LEVEL_GROUP, false),
+            reference("/ws/tests/it.rs", "fn it_handles", true),
+        ];
+        let summary = summarize_references(&references);
+        assert_eq!((summary.total, summary.in_tests), (4, 1));
+        assert_eq!(summary.files.len(), 2);
+        assert_eq!(summary.files[0].count, 3);
+        assert_eq!(summary.files[0].items[0].count, 2);
+        assert_eq!(
+            format_reference_summary(&summary),
+            "4 references in 2 files; 1 in tests\n\n/ws/src/lib.rs (3)\n  impl Server > fn handle (2)\n  (top level) (1)\n\n/ws/tests/it.rs (1)\n  fn it_handles (1) [test]\n"
+        );
+
+        // Groups expand by path suffix and by full, last or outer item
+        let in_group = |file, item| {
+            references
+                .iter()
+                .filter(|reference| in_reference_group(reference, file, item))
+                .count()
+        };
+        assert_eq!(in_group(Some("src/lib.rs"), None), 3);
+        assert_eq!(in_group(Some("lib.rs"), Some("handle")), 2);
+        assert_eq!(in_group(None, Some("fn handle")), 2);
+        assert_eq!(in_group(None, Some("impl Server")), 2);
+        assert_eq!(in_group(None, Some("Server")), 0);
+
+        let page = PageInfo {
+            total: 4,
+            offset: 0,
+            returned: 2,
+            per_file: Vec::new(),
+            next_cursor: Some("offset:2".to_string()),
+        };
+        let rendered = [
+            "/ws/src/lib.rs:1:1\n".to_string(),
+            "/ws/src/lib.rs:1:1\n".to_string(),
+        ];
+        assert_eq!(
+            format_grouped_references(&summary, &page, &references[..2], &rendered),
+            "4 references in 2 files; 1 in tests\nShowing references 1-2 of 4; next page: cursor \"offset:2\"\n\n/ws/src/lib.rs (3)\n  impl Server > fn handle (2)\n    /ws/src/lib.rs:1:1\n\n    /ws/src/lib.rs:1:1\n"
+        );
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("src/**", "src/mcp/server.rs"));
+        assert!(glob_matches("src/**/*.rs", "src/lib.rs"));
+        assert!(glob_matches("crates/*/src/**", "crates/core/src/a/b.rs"));
+        assert!(!glob_matches("crates/*/src/**", "crates/core/tests/a.rs"));
+        assert!(glob_matches("**/tests/**", "crates/core/tests/a.rs"));
+        assert!(glob_matches("*_test.rs", "src/parser_test.rs"));
+        assert!(glob_matches("lib.?s", "src/lib.rs"));
+        assert!(!glob_matches("src/*.rs", "src/mcp/server.rs"));
+        assert!(glob_matches(
+            "/home/*/.cargo/registry/**",
+            "/home/me/.cargo/registry/src/serde/lib.rs"
+        ));
+        assert!(glob_segment_matches("a*b*c", "axxbyyc"));
+        assert!(!glob_segment_matches("a*b*c", "axxbyy"));
+    }
+
+    #[test]
+    fn test_parse_hover_text() {
+        let hover = parse_hover_text(
+            "```rust\nsample_project\n```\n\n```rust\npub fn add(a: i32, b: i32) -> i32\n```\n\n---\n\nAdds two numbers.\n",
+        );
+        assert_eq!(
+            hover.signature.as_deref(),
+            Some("pub fn add(a: i32, b: i32) -> i32")
+        );
+        assert_eq!(hover.documentation.as_deref(), Some("Adds two numbers."));
+
+        let hover = parse_hover_text("```rust\nlet x: i32\n```");
+        assert_eq!(hover.signature.as_deref(), Some("let x: i32"));
+        assert_eq!(hover.documentation, None);
+
+        let hover = parse_hover_text("plain text");
+        assert_eq!(hover.signature, None);
+        assert_eq!(hover.documentation.as_deref(), Some("plain text"));
+    }
+
+    #[test]
+    fn test_tools_declare_output_schemas() {
+        let tools = KadabraRunes::tool_router().list_all();
+        let schema = |name: &str| {
+            tools
+                .iter()
+                .find(|tool| tool.name == name)
+                .and_then(|tool| tool.output_schema.clone())
+        };
+
+        let locations = schema("find_references").expect("find_references declares a schema");
+        assert!(locations["properties"]["locations"].is_object());
+        assert!(schema("hover").is_some());
+        assert!(schema("workspace_symbols").is_some());
+        assert!(schema("incoming_calls").is_some());
+        assert!(schema("module_tree").is_none());
+    }
 
     #[test]
     fn test_helper_functions() {
@@ -0,0 +1,329 @@
+//! Rust path addressing for symbols.
+//!
+//! Tools accept paths such as `my_crate::shapes::Square::area` or
+//! `<Square as Area>::area` instead of a file position. This module parses such
+//! paths and matches them against the qualified paths of candidate symbols; the
+//! server resolves them through workspace and document symbols.
+//!
+//! Resolution rules:
+//! - A leading `crate` or crate name anchors the path at the crate root; any other
+//!   path matches as a suffix, so `Square::area` finds `crate::shapes::Square::area`.
+//! - Methods of an impl block are addressed through the impl's self type with generic
+//!   arguments dropped: `Wrapper::new` for `impl<T> Wrapper<T> { fn new() }`.
+//! - `Trait::method` addresses the declaration in the trait, never an implementation.
+//! - `<Type as Trait>::method` addresses the method in the impl of `Trait` for `Type`;
+//!   `<T as Trait>::method` or `<_ as Trait>::method` reach blanket impls.
+//! - When a path matches both an inherent method and trait methods, the inherent
+//!   method wins, as in Rust's method resolution.
+
+use std::path::Path;
+
+use lsp_types::{DocumentSymbol, Range, SymbolKind};
+
+/// A parsed Rust path addressing a symbol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolPath {
+    /// Path segments without generic arguments, e.g. `["crate", "shapes", "Square", "area"]`.
+    ///
+    /// For `<Type as Trait>::item` these are the segments of `Type::item`.
+    pub segments: Vec<String>,
+    /// Trait segments of a `<Type as Trait>::item` path.
+    pub as_trait: Option<Vec<String>>,
+}
+
+/// Returns whether a symbol query looks like a Rust path rather than a plain name.
+pub fn is_symbol_path(query: &str) -> bool {
+    query.contains("::") || query.trim_start().starts_with('<')
+}
+
+/// Parses a Rust path such as `crate::module::Type::method` or `<Type as Trait>::method`.
+///
+/// Returns `None` if the path is malformed.
+pub fn parse_symbol_path(input: &str) -> Option<SymbolPath> {
+    let input = input.trim().trim_start_matches("::");
+
+    let (mut segments, as_trait) = match input.strip_prefix('<') {
+        Some(qualified) => {
+            let close = matching_angle_bracket(qualified)?;
+            let rest = qualified[close + 1..].strip_prefix("::")?;
+            let (self_type, as_trait) = match split_top_level(&qualified[..close], " as ") {
+                Some((self_type, as_trait)) => (self_type, Some(split_path(as_trait)?)),
+                None => (&qualified[..close], None),
+            };
+            let mut segments = split_path(self_type.trim().trim_start_matches(['&', '*']))?;
+            segments.extend(split_path(rest)?);
+            (segments, as_trait)
+        }
+        None => (split_path(input)?, None),
+    };
+
+    // `self::` and `super::` are relative to a module we do not know; match as a suffix
+    while segments
+        .first()
+        .is_some_and(|segment| segment == "self" || segment == "super")
+    {
+        segments.remove(0);
+    }
+    if segments.is_empty() || segments.last().is_some_and(|segment| segment == "crate") {
+        return None;
+    }
+
+    Some(SymbolPath { segments, as_trait })
+}
+
+/// Splits a path at top-level `::`, dropping generic arguments from each segment.
+fn split_path(path: &str) -> Option<Vec<String>> {
+    let mut segments = Vec::new();
+    let mut depth = 0_usize;
+    let mut current = String::new();
+    let mut chars = path.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth = depth.checked_sub(1)?,
+            ':' if depth == 0 && chars.peek() == Some(&':') => {
+                chars.next();
+                segments.push(std::mem::take(&mut current));
+            }
+            _ if depth == 0 => current.push(c),
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return None;
+    }
+    segments.push(current);
+
+    segments
+        .into_iter()
+        .map(|segment| {
+            let segment = segment.trim();
+            let is_identifier =
+                !segment.is_empty() && segment.chars().all(|c| c.is_alphanumeric() || c == '_');
+            is_identifier.then(|| segment.to_string())
+        })
+        .collect()
+}
+
+/// Returns the byte index of the `>` closing an opened `<`, given the text after it.
+fn matching_angle_bracket(text: &str) -> Option<usize> {
+    let mut depth = 1_usize;
+    for (index, c) in text.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Splits `text` at the first occurrence of `separator` outside angle brackets.
+fn split_top_level<'a>(text: &'a str, separator: &str) -> Option<(&'a str, &'a str)> {
+    let mut depth = 0_usize;
+    for (index, c) in text.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth = depth.saturating_sub(1),
+            _ if depth == 0 && text[index..].starts_with(separator) => {
+                return Some((&text[..index], &text[index + separator.len()..]));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the last segment of a type or trait path without generic arguments.
+fn last_segment(path: &str) -> Option<String> {
+    let path = path
+        .trim()
+        .trim_start_matches(['&', '!'])
+        .trim_start_matches("mut ")
+        .trim_start_matches("dyn ");
+    split_path(path)?.pop()
+}
+
+/// Parses the name of an impl block's document symbol, e.g. `impl Area for Square`.
+///
+/// Returns the trait (if any) and the self type, both as their last path segment
+/// without generic arguments.
+pub fn impl_trait_and_self_type(name: &str) -> Option<(Option<String>, String)> {
+    let rest = name.strip_prefix("impl")?;
+    if !rest.starts_with([' ', '<']) {
+        return None;
+    }
+    // rust-analyzer omits the impl's own generic parameters, other servers may not
+    let rest = match rest.strip_prefix('<') {
+        Some(generic) => &generic[matching_angle_bracket(generic)? + 1..],
+        None => rest,
+    };
+
+    match split_top_level(rest, " for ") {
+        Some((as_trait, self_type)) => {
+            Some((Some(last_segment(as_trait)?), last_segment(self_type)?))
+        }
+        None => Some((None, last_segment(rest)?)),
+    }
+}
+
+/// Returns the path segment a document symbol contributes to the paths of its children.
+///
+/// Impl blocks contribute their self type, everything else its name.
+pub fn symbol_path_segment(symbol: &DocumentSymbol) -> String {
+    if symbol.kind == SymbolKind::OBJECT
+        && let Some((_, self_type)) = impl_trait_and_self_type(&symbol.name)
+    {
+        return self_type;
+    }
+    symbol.name.clone()
+}
+
+/// Returns the nested document symbols from the top level down to the symbol whose
+/// name is at `selection`, or `None` if no symbol is there.
+pub fn document_symbol_chain(
+    symbols: &[DocumentSymbol],
+    selection: Range,
+) -> Option<Vec<&DocumentSymbol>> {
+    for symbol in symbols {
+        if symbol.selection_range == selection {
+            return Some(vec![symbol]);
+        }
+        if symbol.range.start <= selection.start
+            && selection.end <= symbol.range.end
+            && let Some(mut chain) =
+                document_symbol_chain(symbol.children.as_deref().unwrap_or_default(), selection)
+        {
+            chain.insert(0, symbol);
+            return Some(chain);
+        }
+    }
+    None
+}
+
+/// Returns whether a symbol's path below its crate root matches a queried path.
+///
+/// A query starting with `crate` or the crate's name must match the whole path;
+/// any other query matches a suffix of it.
+pub fn symbol_path_matches(
+    candidate: &[String],
+    query: &[String],
+    crate_name: Option<&str>,
+) -> bool {
+    match query.split_first() {
+        Some((first, rest)) if first == "crate" => candidate == rest,
+        Some((first, rest)) if Some(first.as_str()) == crate_name => {
+            candidate == rest || candidate.ends_with(query)
+        }
+        _ => candidate.ends_with(query),
+    }
+}
+
+/// Returns the name by which code refers to the crate containing `path`.
+///
+/// Reads `[package] name` from the nearest `Cargo.toml`, with `-` replaced by `_`.
+pub fn crate_name_for(path: &Path) -> Option<String> {
+    path.ancestors()
+        .skip(1)
+        .find_map(|dir| package_name(&std::fs::read_to_string(dir.join("Cargo.toml")).ok()?))
+        .map(|name| name.replace('-', "_"))
+}
+
+/// Extracts `[package] name` from a `Cargo.toml` manifest.
+fn package_name(manifest: &str) -> Option<String> {
+    let mut in_package = false;
+    for line in manifest.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+        } else if in_package
+            && let Some(value) = line.strip_prefix("name")
+            && let Some(value) = value.trim_start().strip_prefix('=')
+        {
+            return Some(value.trim().trim_matches('"').to_string());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(path: &[&str]) -> Vec<String> {
+        path.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_parse_symbol_path() {
+        let path = parse_symbol_path("crate::shapes::Square::area").unwrap();
+        assert_eq!(
+            path.segments,
+            segments(&["crate", "shapes", "Square", "area"])
+        );
+        assert_eq!(path.as_trait, None);
+
+        let path = parse_symbol_path("self::Wrapper<Vec<u8>>::new").unwrap();
+        assert_eq!(path.segments, segments(&["Wrapper", "new"]));
+
+        let path = parse_symbol_path("<shapes::Square as crate::Area>::area").unwrap();
+        assert_eq!(path.segments, segments(&["shapes", "Square", "area"]));
+        assert_eq!(path.as_trait, Some(segments(&["crate", "Area"])));
+
+        let path = parse_symbol_path("<_ as Describe<u8>>::describe").unwrap();
+        assert_eq!(path.segments, segments(&["_", "describe"]));
+        assert_eq!(path.as_trait, Some(segments(&["Describe"])));
+
+        assert!(parse_symbol_path("crate::").is_none());
+        assert!(parse_symbol_path("Vec<u8::new").is_none());
+        assert!(parse_symbol_path("a::b c").is_none());
+        assert!(!is_symbol_path("area"));
+        assert!(is_symbol_path("Square::area"));
+    }
+
+    #[test]
+    fn test_impl_trait_and_self_type() {
+        assert_eq!(
+            impl_trait_and_self_type("impl Area for Square"),
+            Some((Some("Area".to_string()), "Square".to_string()))
+        );
+        assert_eq!(
+            impl_trait_and_self_type("impl Wrapper<T>"),
+            Some((None, "Wrapper".to_string()))
+        );
+        assert_eq!(
+            impl_trait_and_self_type("impl<T: Clone> Default for Wrapper<Option<T>>"),
+            Some((Some("Default".to_string()), "Wrapper".to_string()))
+        );
+        assert_eq!(
+            impl_trait_and_self_type("impl std::fmt::Display for &mut crate::Square"),
+            Some((Some("Display".to_string()), "Square".to_string()))
+        );
+        assert_eq!(impl_trait_and_self_type("Square"), None);
+    }
+
+    #[test]
+    fn test_symbol_path_matches() {
+        let candidate = segments(&["shapes", "Square", "area"]);
+        let matches =
+            |query: &[&str]| symbol_path_matches(&candidate, &segments(query), Some("geometry"));
+
+        assert!(matches(&["Square", "area"]));
+        assert!(matches(&["crate", "shapes", "Square", "area"]));
+        assert!(matches(&["geometry", "shapes", "Square", "area"]));
+        assert!(!matches(&["crate", "Square", "area"]));
+        assert!(!matches(&["Circle", "area"]));
+    }
+
+    #[test]
+    fn test_package_name() {
+        let manifest = "[workspace]\nname = \"ignored\"\n\n[package]\nname = \"kadabra-runes\"\nversion = \"0.1.0\"\n";
+        assert_eq!(package_name(manifest), Some("kadabra-runes".to_string()));
+        assert_eq!(package_name("[workspace]\nmembers = []\n"), None);
+    }
+}
@@ -13,6 +13,7 @@
 //!
 //! ## High Priority (Must Have)
 //! - `goto_definition` - Jump to symbol definition
+//! - `goto_declaration` - Jump to symbol declaration (e.g. trait method, header)
 //! - `find_references` - Find all references to a symbol
 //! - `hover` - Get type info and documentation
 //! - `document_symbols` - List symbols in a file
@@ -21,9 +22,33 @@
 //! - `outgoing_calls` - Find functions called by a function
 //! - `implementations` - Find implementations of a trait/interface
 //! - `type_definition` - Jump to type definition
+//! - `type_hierarchy` - Show supertypes and subtypes of a type
+//! - `document_highlights` - Find read/write occurrences of a symbol in a file
+//! - `inlay_hints` - Show inferred types and parameter names for a line range
+//! - `completions` - List members and items available at a position
+//! - `expand_macro` - Show the recursive expansion of a macro call (rust-analyzer)
+//! - `runnables` - List binaries, tests and benches in a file (rust-analyzer)
+//! - `related_tests` - Find tests exercising a function (rust-analyzer)
+//! - `module_tree` - Show how a file is reached from its crate root and its submodules (rust-analyzer)
+//! - `ssr_preview` - Preview a structural search and replace rule as a diff (rust-analyzer)
+//! - `view_syntax_tree` - Show the syntax tree of the item at a position (rust-analyzer)
+//! - `view_hir` - Show the HIR of the function at a position (rust-analyzer)
+//! - `view_mir` - Show the MIR of the function at a position (rust-analyzer)
+//! - `external_docs` - Resolve docs.rs and local `cargo doc` URLs of an item (rust-analyzer)
+//! - `diagnostics` - Get the errors and warnings reported for a file or the open files
+//! - `check_workspace` - Run cargo check/clippy and collect diagnostics (rust-analyzer)
+//!
+//! ## Server Maintenance (rust-analyzer)
+//! - `reload_workspace` - Reload the workspace after `Cargo.toml` changes
+//! - `rebuild_proc_macros` - Re-run build scripts and rebuild proc macros
+//! - `analyzer_status` - Report rust-analyzer's internal state
+//! - `memory_usage` - Report rust-analyzer's memory usage
+//!
+//! ## Project Structure (rust-analyzer)
+//! - `crate_graph` - Export the crate dependency graph as DOT, Mermaid or JSON
+//! - `dependencies` - List dependencies with versions and source roots
 //!
 //! ## Nice to Have (Future)
-//! - `diagnostics` - Get errors and warnings
 //! - `signature_help` - Get function signature info
 //! - `rename_preview` - Preview rename refactoring
 //! - `code_actions` - Get available quick fixes
@@ -48,30 +73,59 @@ use serde::{Deserialize, Serialize};
 // }
 // ```
 
-/// Defines position in the file - path, line and column number.
+/// Defines position in the file - path, line and column number or token - or a Rust path.
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PositionParams {
     /// Path to the file.
-    #[schemars(description = "Absolute path to the source file")]
+    #[serde(default)]
+    #[schemars(
+        description = "Path to the source file, relative to the workspace or as shown in tool output (omit when using symbolPath)"
+    )]
     pub file_path: String,
     /// Line number (1-indexed for user-friendliness).
+    #[serde(default)]
     #[schemars(description = "Line number (1-indexed)")]
     pub line: u32,
     /// Column number (1-indexed for user-friendliness).
-    #[schemars(description = "Column number (1-indexed)")]
+    #[serde(default)]
+    #[schemars(
+        description = "Column number (1-indexed); on whitespace or punctuation it snaps to the nearest identifier"
+    )]
     pub column: u32,
+    /// Token on `line` to anchor the column to, instead of an exact column.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(
+        description = "Identifier (or other text) on the given line to point at, instead of an exact column"
+    )]
+    pub symbol_text: Option<String>,
+    /// Which occurrence of `symbol_text` on the line to use.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(
+        description = "1-based occurrence of symbolText on the line when it appears more than once (default: the one nearest column, else the first)"
+    )]
+    pub occurrence: Option<u32>,
+    /// Rust path of the symbol, used instead of file path, line and column.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(
+        description = "Rust path of the symbol instead of filePath/line/column, e.g. `my_crate::module::Type::method`, `Type::method`, `Trait::method` (the declaration) or `<Type as Trait>::method` (the impl)"
+    )]
+    pub symbol_path: Option<String>,
 }
 
 /// Input for symbol-based queries by name with an optional file path filter.
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SymbolNameParams {
-    /// The symbol name to search for.
-    #[schemars(description = "The symbol name to search for")]
+    /// The symbol name or Rust path to search for.
+    #[schemars(
+        description = "The symbol name to search for, or a Rust path such as `Type::method` or `<Type as Trait>::method`"
+    )]
     pub symbol: String,
     /// Optional file path to narrow the search.
-    #[schemars(description = "Optional file path to narrow the search scope")]
+    #[schemars(
+        description = "Optional file path to narrow the search scope, relative to the workspace or as shown in tool output"
+    )]
     pub file_path: Option<String>,
 }
 
@@ -85,14 +139,128 @@ pub enum SymbolQuery {
     Name(SymbolNameParams),
 }
 
-// /// Parameters for the `goto_definition` tool.
-// #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-// #[serde(rename_all = "camelCase")]
-// pub struct GotoDefinitionParams {
-//     /// The symbol to find the definition of.
-//     #[schemars(description = "The symbol to find the definition of by pointing position in file")]
-//     pub query: PositionParams,
-// }
+/// Paging and output budget for tools returning lists of results.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PageParams {
+    /// Maximum number of results on this page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(
+        description = "Maximum number of results to return on this page, at least 1 (default: 100)"
+    )]
+    pub limit: Option<u32>,
+    /// Number of results to skip.
+    #[serde(default)]
+    #[schemars(description = "Number of results to skip (default: 0); ignored when cursor is set")]
+    pub offset: u32,
+    /// Continuation token returned by a previous, truncated page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(description = "Continuation token from a previous page's nextCursor")]
+    pub cursor: Option<String>,
+    /// Maximum number of characters of results to return.
+    #[serde(default = "default_max_output_chars")]
+    #[schemars(
+        description = "Maximum number of characters of results to return; results that do not fit move to the next page (default: 20000)"
+    )]
+    pub max_output_chars: u32,
+}
+
+impl Default for PageParams {
+    fn default() -> Self {
+        Self {
+            limit: None,
+            offset: 0,
+            cursor: None,
+            max_output_chars: default_max_output_chars(),
+        }
+    }
+}
+
+/// Filters for tools returning results from across the workspace and its dependencies.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterParams {
+    /// Whether to include results in test code.
+    #[serde(default = "default_include")]
+    #[schemars(
+        description = "Include results in test code: files under a tests/ directory, #[cfg(test)] modules and #[test] functions (default: true)"
+    )]
+    pub include_tests: bool,
+    /// Whether to include results outside the workspace.
+    #[serde(default = "default_include")]
+    #[schemars(
+        description = "Include results outside the workspace root, such as dependencies in ~/.cargo/registry and the standard library sources (default: true)"
+    )]
+    pub include_external: bool,
+    /// Globs of paths to keep.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schemars(
+        description = "Only keep results whose path matches one of these globs, e.g. `src/**` or `crates/*/src/**`; paths inside the workspace are matched relative to its root, and a glob without `/` matches the file name"
+    )]
+    pub include_paths: Vec<String>,
+    /// Globs of paths to drop.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schemars(
+        description = "Drop results whose path matches any of these globs, e.g. `benches/**` or `*_generated.rs`"
+    )]
+    pub exclude_paths: Vec<String>,
+}
+
+impl Default for FilterParams {
+    fn default() -> Self {
+        Self {
+            include_tests: true,
+            include_external: true,
+            include_paths: Vec::new(),
+            exclude_paths: Vec::new(),
+        }
+    }
+}
+
+fn default_include() -> bool {
+    true
+}
+
+/// How much source context to show around each returned location.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum LocationContext {
+    /// This many lines before and after the location.
+    Lines(u32),
+    /// A named context mode.
+    Mode(ContextMode),
+}
+
+impl Default for LocationContext {
+    fn default() -> Self {
+        Self::Lines(2)
+    }
+}
+
+/// Named modes of [`LocationContext`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ContextMode {
+    /// Locations only, without source lines.
+    None,
+    /// The signature and line span of the innermost item containing the location.
+    Enclosing,
+}
+
+/// Parameters for the `goto_definition` and `goto_declaration` tools.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GotoParams {
+    /// Position of the symbol to jump from.
+    #[serde(flatten)]
+    pub position: PositionParams,
+    /// How much source context to show around each location.
+    #[serde(default)]
+    #[schemars(
+        description = "Source context per location: a number of lines around it (default: 2, at most 50), `none` for locations only, or `enclosing` for the signature and line span of the containing item"
+    )]
+    pub context: LocationContext,
+}
 
 /// Parameters for the `find_references` tool.
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -105,6 +273,36 @@ pub struct FindReferencesParams {
     #[serde(default)]
     #[schemars(description = "Whether to include the declaration in the results (default: false)")]
     pub include_declaration: bool,
+    /// How much source context to show around each location.
+    #[serde(default)]
+    #[schemars(
+        description = "Source context per location: a number of lines around it (default: 2, at most 50), `none` for locations only, or `enclosing` for the signature and line span of the containing item"
+    )]
+    pub context: LocationContext,
+    /// Whether to return only the reference counts per group.
+    #[serde(default)]
+    #[schemars(
+        description = "Return only the reference counts per file and enclosing item, without the references themselves (default: false)"
+    )]
+    pub summary_only: bool,
+    /// File group to expand.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(
+        description = "Only list references in this file, given as shown in the summary or as a path suffix such as `src/server.rs`"
+    )]
+    pub group_file: Option<String>,
+    /// Enclosing item group to expand.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(
+        description = "Only list references inside this item, given as shown in the summary (e.g. `impl Server > fn handle_request`) or by its last part (`fn handle_request` or `handle_request`)"
+    )]
+    pub group_item: Option<String>,
+    /// Which results to keep.
+    #[serde(flatten)]
+    pub filter: FilterParams,
+    /// Which page of references to return.
+    #[serde(flatten)]
+    pub page: PageParams,
 }
 
 /// Parameters for the hover tool.
@@ -121,7 +319,9 @@ pub struct HoverParams {
 #[serde(rename_all = "camelCase")]
 pub struct DocumentSymbolsParams {
     /// Path to the file.
-    #[schemars(description = "Absolute path to the source file to list symbols from")]
+    #[schemars(
+        description = "Path to the source file to list symbols from, relative to the workspace or as shown in tool output"
+    )]
     pub file_path: String,
 }
 
@@ -132,10 +332,18 @@ pub struct WorkspaceSymbolsParams {
     /// Query string to search for.
     #[schemars(description = "Query string to search for symbols across the workspace")]
     pub query: String,
-    /// Maximum number of results to return.
+    /// Maximum number of results to return when `limit` is not given.
     #[serde(default = "default_max_results")]
-    #[schemars(description = "Maximum number of results to return (default: 50)")]
+    #[schemars(
+        description = "Maximum number of results to return when limit is not given (default: 50)"
+    )]
     pub max_results: u32,
+    /// Which results to keep.
+    #[serde(flatten)]
+    pub filter: FilterParams,
+    /// Which page of symbols to return.
+    #[serde(flatten)]
+    pub page: PageParams,
 }
 
 fn default_max_results() -> u32 {
@@ -149,6 +357,12 @@ pub struct IncomingCallsParams {
     /// Position of the function to find callers for.
     #[schemars(description = "Position of the function to find callers for")]
     pub position: PositionParams,
+    /// Which callers to keep.
+    #[serde(flatten)]
+    pub filter: FilterParams,
+    /// Which page of calls to return.
+    #[serde(flatten)]
+    pub page: PageParams,
 }
 
 /// Parameters for the `outgoing_calls` tool.
@@ -158,6 +372,9 @@ pub struct OutgoingCallsParams {
     /// Position of the function to find callees for.
     #[schemars(description = "Position of the function to find callees for")]
     pub position: PositionParams,
+    /// Which page of calls to return.
+    #[serde(flatten)]
+    pub page: PageParams,
 }
 
 /// Parameters for the implementations tool.
@@ -169,6 +386,18 @@ pub struct ImplementationsParams {
         description = "The trait/interface to find implementations for (by position or name)"
     )]
     pub query: SymbolQuery,
+    /// How much source context to show around each location.
+    #[serde(default)]
+    #[schemars(
+        description = "Source context per location: a number of lines around it (default: 2, at most 50), `none` for locations only, or `enclosing` for the signature and line span of the containing item"
+    )]
+    pub context: LocationContext,
+    /// Which results to keep.
+    #[serde(flatten)]
+    pub filter: FilterParams,
+    /// Which page of implementations to return.
+    #[serde(flatten)]
+    pub page: PageParams,
 }
 
 /// Parameters for the `type_definition` tool.
@@ -178,21 +407,286 @@ pub struct TypeDefinitionParams {
     /// Position to get type definition for.
     #[schemars(description = "Position in the file to get type definition for")]
     pub position: PositionParams,
+    /// How much source context to show around each location.
+    #[serde(default)]
+    #[schemars(
+        description = "Source context per location: a number of lines around it (default: 2, at most 50), `none` for locations only, or `enclosing` for the signature and line span of the containing item"
+    )]
+    pub context: LocationContext,
+}
+
+/// Parameters for the `type_hierarchy` tool.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeHierarchyParams {
+    /// Position of the type to build the hierarchy for.
+    #[schemars(description = "Position of the type or trait to build the hierarchy for")]
+    pub position: PositionParams,
+    /// How many levels of supertypes and subtypes to expand.
+    #[serde(default = "default_hierarchy_depth")]
+    #[schemars(description = "How many levels of supertypes and subtypes to expand (default: 2)")]
+    pub depth: u32,
+}
+
+fn default_hierarchy_depth() -> u32 {
+    2
+}
+
+/// Parameters for the `document_highlights` tool.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentHighlightsParams {
+    /// Position of the symbol to highlight.
+    #[schemars(description = "Position of the symbol to find occurrences of in the same file")]
+    pub position: PositionParams,
+}
+
+/// Parameters for the `inlay_hints` tool.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct InlayHintsParams {
+    /// Path to the file.
+    #[schemars(
+        description = "Path to the source file, relative to the workspace or as shown in tool output"
+    )]
+    pub file_path: String,
+    /// First line of the range (1-indexed, inclusive).
+    #[schemars(description = "First line of the range to render (1-indexed, inclusive)")]
+    pub start_line: u32,
+    /// Last line of the range (1-indexed, inclusive).
+    #[schemars(description = "Last line of the range to render (1-indexed, inclusive)")]
+    pub end_line: u32,
+}
+
+/// Parameters for the `completions` tool.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionsParams {
+    /// Position to list completions at.
+    #[schemars(
+        description = "Position to list completions at, e.g. just after `value.` to list its methods and fields"
+    )]
+    pub position: PositionParams,
+    /// Maximum number of results to return.
+    #[serde(default = "default_max_results")]
+    #[schemars(description = "Maximum number of results to return (default: 50)")]
+    pub max_results: u32,
+}
+
+/// Parameters for the `expand_macro` tool.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpandMacroParams {
+    /// Position of the macro call or derive attribute.
+    #[schemars(description = "Position inside a macro call or derive/attribute macro")]
+    pub position: PositionParams,
+    /// Maximum number of characters of output to return.
+    #[serde(default = "default_max_output_chars")]
+    #[schemars(description = "Maximum number of characters of output to return (default: 20000)")]
+    pub max_chars: u32,
+}
+
+fn default_max_output_chars() -> u32 {
+    20_000
+}
+
+/// Parameters for the `runnables` tool.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RunnablesParams {
+    /// Path to the file.
+    #[schemars(
+        description = "Path to the source file to list runnables from, relative to the workspace or as shown in tool output"
+    )]
+    pub file_path: String,
+}
+
+/// Parameters for the `related_tests` tool.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RelatedTestsParams {
+    /// Position of the function or item to find tests for.
+    #[schemars(description = "Position of the function, method or type to find tests for")]
+    pub position: PositionParams,
+}
+
+/// Parameters for the `module_tree` tool.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleTreeParams {
+    /// Path to the crate root or module file.
+    #[schemars(
+        description = "Path to a crate root (lib.rs, main.rs) or module file, relative to the workspace or as shown in tool output"
+    )]
+    pub file_path: String,
+    /// How many levels of submodules to show below the file's module.
+    #[serde(default = "default_module_depth")]
+    #[schemars(description = "How many levels of submodules to show (default: 3)")]
+    pub depth: u32,
+}
+
+fn default_module_depth() -> u32 {
+    3
+}
+
+/// Parameters for the `ssr_preview` tool.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SsrPreviewParams {
+    /// The structural search and replace rule.
+    #[schemars(
+        description = "SSR rule, e.g. `foo($a, $b) ==>> bar($b, $a)`. Placeholders like $a match any expression; paths match by resolution, not text"
+    )]
+    pub rule: String,
+    /// File whose scope resolves paths in the rule.
+    #[schemars(
+        description = "Path to a file whose module scope is used to resolve paths in the rule; with scope `file`, the only file searched"
+    )]
+    pub file_path: String,
+    /// Where to search for matches.
+    #[serde(default)]
+    #[schemars(description = "Where to search: `workspace` (default) or `file`")]
+    pub scope: SsrScope,
+    /// Maximum number of characters of output to return.
+    #[serde(default = "default_max_output_chars")]
+    #[schemars(description = "Maximum number of characters of output to return (default: 20000)")]
+    pub max_chars: u32,
+}
+
+/// Where `ssr_preview` searches for matches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum SsrScope {
+    /// Search the whole workspace.
+    #[default]
+    Workspace,
+    /// Search only the file given by `file_path`.
+    File,
+}
+
+/// Parameters for the `analyzer_status` tool.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyzerStatusParams {
+    /// Optional file whose crates should be included in the report.
+    #[schemars(
+        description = "Optional path to a file; the report then includes the crates it belongs to"
+    )]
+    pub file_path: Option<String>,
+    /// Maximum number of characters of output to return.
+    #[serde(default = "default_max_output_chars")]
+    #[schemars(description = "Maximum number of characters of output to return (default: 20000)")]
+    pub max_chars: u32,
+}
+
+/// Parameters for the `crate_graph` tool.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CrateGraphParams {
+    /// Output format of the graph.
+    #[serde(default)]
+    #[schemars(description = "Output format: `dot` (default), `mermaid` or `json`")]
+    pub format: GraphFormat,
+    /// Whether to include all dependencies or only workspace members.
+    #[serde(default)]
+    #[schemars(
+        description = "Include all dependencies, including std and registry crates (default: false, workspace members only)"
+    )]
+    pub full: bool,
+    /// Maximum number of characters of output to return.
+    #[serde(default = "default_max_output_chars")]
+    #[schemars(description = "Maximum number of characters of output to return (default: 20000)")]
+    pub max_chars: u32,
+}
+
+/// Output format of the `crate_graph` tool.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum GraphFormat {
+    /// Graphviz DOT.
+    #[default]
+    Dot,
+    /// Mermaid flowchart.
+    Mermaid,
+    /// JSON with `crates` and `dependencies` arrays.
+    Json,
+}
+
+/// Parameters for the `view_syntax_tree`, `view_hir` and `view_mir` tools.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemViewParams {
+    /// Position inside the item to inspect.
+    #[schemars(description = "Position inside the function or item to inspect")]
+    pub position: PositionParams,
+    /// Maximum number of characters of output to return.
+    #[serde(default = "default_max_output_chars")]
+    #[schemars(description = "Maximum number of characters of output to return (default: 20000)")]
+    pub max_chars: u32,
+}
+
+/// Parameters for the `external_docs` tool.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalDocsParams {
+    /// Position of the item to find documentation for.
+    #[schemars(
+        description = "Position of the item, e.g. a type, function or method, to find documentation for"
+    )]
+    pub position: PositionParams,
+}
+
+/// Parameters for the `diagnostics` tool.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsParams {
+    /// Optional file to report diagnostics for.
+    #[schemars(
+        description = "Optional path to a file; when omitted, diagnostics for all open files are returned"
+    )]
+    pub file_path: Option<String>,
+    /// Maximum number of characters of output to return.
+    #[serde(default = "default_max_output_chars")]
+    #[schemars(description = "Maximum number of characters of output to return (default: 20000)")]
+    pub max_chars: u32,
+}
+
+/// Parameters for the `check_workspace` tool.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckWorkspaceParams {
+    /// Optional file whose workspace should be checked.
+    #[schemars(
+        description = "Optional path to a file; only the workspace containing it is checked"
+    )]
+    pub file_path: Option<String>,
+    /// How long to wait for the check to finish.
+    #[serde(default = "default_check_timeout_secs")]
+    #[schemars(description = "Seconds to wait for cargo check to finish (default: 300)")]
+    pub timeout_secs: u64,
+    /// Maximum number of characters of output to return.
+    #[serde(default = "default_max_output_chars")]
+    #[schemars(description = "Maximum number of characters of output to return (default: 20000)")]
+    pub max_chars: u32,
+}
+
+fn default_check_timeout_secs() -> u64 {
+    300
 }
 
 /// A location in the source code with context.
-/// Note: Currently unused - reserved for future structured JSON responses.
-#[allow(dead_code)]
+///
+/// Part of the structured output of the location-returning tools.
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct LocationWithContext {
-    /// Path to the file.
+    /// Path to the file, in the short form of [`crate::mcp::paths`].
     pub file_path: String,
     /// Line number (1-indexed).
     pub line: u32,
     /// Column number (1-indexed).
     pub column: u32,
-    /// The source code line at this location.
+    /// The source code line at this location; empty in the `none` context mode.
     pub context: String,
     /// Additional context lines before.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -200,11 +694,28 @@ pub struct LocationWithContext {
     /// Additional context lines after.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub context_after: Option<Vec<String>>,
+    /// The innermost item containing this location, in the `enclosing` context mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enclosing: Option<EnclosingItem>,
+}
+
+/// The item containing a location, such as the function around a reference.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EnclosingItem {
+    /// The item as written in Rust, e.g. `fn handle_request` or `impl Display for Point`.
+    pub name: String,
+    /// First line of the item (1-indexed), including doc comments and attributes.
+    pub start_line: u32,
+    /// Last line of the item (1-indexed).
+    pub end_line: u32,
+    /// Line number (1-indexed) of the first signature line.
+    pub signature_line: u32,
+    /// Source lines of the item's signature, up to the start of its body.
+    pub signature: Vec<String>,
 }
 
 /// A symbol with its location.
-/// Note: Currently unused - reserved for future structured JSON responses.
-#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SymbolInfo {
@@ -220,9 +731,7 @@ pub struct SymbolInfo {
 }
 
 /// Result of a hover operation.
-/// Note: Currently unused - reserved for future structured JSON responses.
-#[allow(dead_code)]
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct HoverResult {
     /// The type signature or declaration.
@@ -234,8 +743,6 @@ pub struct HoverResult {
 }
 
 /// Information about a call relationship.
-/// Note: Currently unused - reserved for future structured JSON responses.
-#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CallInfo {
@@ -245,6 +752,140 @@ pub struct CallInfo {
     pub call_sites: Vec<LocationWithContext>,
 }
 
+/// Structured output of `goto_definition`, `goto_declaration`, `find_references`,
+/// `implementations` and `type_definition`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationsResult {
+    /// The locations found.
+    pub locations: Vec<LocationWithContext>,
+    /// Position of these locations among all results, for paginated tools.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<PageInfo>,
+    /// Counts of all results by file and enclosing item, for `find_references`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<ReferenceSummary>,
+}
+
+/// Counts of references grouped by file and enclosing item.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceSummary {
+    /// Number of references.
+    pub total: u32,
+    /// Number of references in test code.
+    pub in_tests: u32,
+    /// References per file, in path order.
+    pub files: Vec<FileGroup>,
+}
+
+/// References in one file, part of [`ReferenceSummary`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FileGroup {
+    /// Path to the file.
+    pub file_path: String,
+    /// Number of references in the file.
+    pub count: u32,
+    /// References per enclosing item, in source order.
+    pub items: Vec<ItemGroup>,
+}
+
+/// References inside one item, part of [`FileGroup`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemGroup {
+    /// Enclosing items from the outermost, e.g. `impl Server > fn handle_request`;
+    /// `(top level)` outside any item.
+    pub item: String,
+    /// Number of references inside the item.
+    pub count: u32,
+    /// Whether the item is test code.
+    pub in_tests: bool,
+}
+
+/// Structured output of `document_symbols` and `workspace_symbols`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolsResult {
+    /// The symbols found, parents before their children.
+    pub symbols: Vec<SymbolInfo>,
+    /// Position of these symbols among all results, for paginated tools.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<PageInfo>,
+}
+
+/// Structured output of `incoming_calls` and `outgoing_calls`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CallsResult {
+    /// The callers or callees, each with its call sites.
+    pub calls: Vec<CallInfo>,
+    /// Position of these calls among all results.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<PageInfo>,
+}
+
+/// Where a page of results lies within the full result list.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PageInfo {
+    /// Number of results across all pages.
+    pub total: u32,
+    /// Index of the first result on this page.
+    pub offset: u32,
+    /// Number of results on this page.
+    pub returned: u32,
+    /// Number of results in each file across all pages, most first.
+    pub per_file: Vec<FileCount>,
+    /// Token to pass as `cursor` to fetch the next page, if results remain.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Number of results in one file, part of [`PageInfo`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FileCount {
+    /// Path to the file.
+    pub file_path: String,
+    /// Number of results in the file.
+    pub count: u32,
+}
+
+/// A crate dependency graph.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CrateGraph {
+    /// The crates in the graph.
+    pub crates: Vec<CrateNode>,
+    /// Dependency edges between crates.
+    pub dependencies: Vec<CrateDependency>,
+}
+
+/// A crate in a [`CrateGraph`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CrateNode {
+    /// Identifier of the crate within the graph.
+    pub id: String,
+    /// Crate name. Targets of one package (lib, bin) share a name.
+    pub name: String,
+}
+
+/// A dependency edge in a [`CrateGraph`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CrateDependency {
+    /// Identifier of the depending crate.
+    pub from: String,
+    /// Identifier of the crate depended on.
+    pub to: String,
+    /// Name the dependency is imported under, if renamed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -255,6 +896,9 @@ mod tests {
             file_path: "/path/to/file.rs".to_string(),
             line: 10,
             column: 5,
+            symbol_text: None,
+            occurrence: None,
+            symbol_path: None,
         };
         let json = serde_json::to_string(&params).unwrap();
         assert!(json.contains("filePath"));
@@ -273,4 +917,24 @@ mod tests {
         let query: SymbolQuery = serde_json::from_str(json).unwrap();
         assert!(matches!(query, SymbolQuery::Name { .. }));
     }
+
+    #[test]
+    fn test_goto_params_context_deserialization() {
+        let json = r#"{"filePath": "/path/to/file.rs", "line": 10, "column": 5}"#;
+        let params: GotoParams = serde_json::from_str(json).unwrap();
+        assert_eq!(params.position.line, 10);
+        assert_eq!(params.context, LocationContext::Lines(2));
+
+        let json = r#"{"filePath": "/path/to/file.rs", "line": 10, "column": 5, "context": 5}"#;
+        let params: GotoParams = serde_json::from_str(json).unwrap();
+        assert_eq!(params.context, LocationContext::Lines(5));
+
+        let json =
+            r#"{"filePath": "/path/to/file.rs", "line": 10, "column": 5, "context": "enclosing"}"#;
+        let params: GotoParams = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            params.context,
+            LocationContext::Mode(ContextMode::Enclosing)
+        );
+    }
 }
@@ -4,6 +4,7 @@
 
 pub mod lsp_harness;
 pub mod temp_workspace;
+pub mod tools;
 
 use kadabra_runes::lsp::client::LspClient;
 use std::path::PathBuf;
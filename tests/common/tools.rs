@@ -0,0 +1,55 @@
+//! Helpers for invoking MCP tools in tests.
+
+use kadabra_runes::mcp::tools::{
+    FilterParams, FindReferencesParams, LocationContext, LocationsResult, PageParams,
+    PositionParams, SymbolQuery,
+};
+use rmcp::model::CallToolResult;
+
+/// Creates a fixture of the `run_project` crate whose `src/lib.rs` starts with
+/// `pub fn run() -> u32` on line 1, followed by `lib_rs` and then by the further
+/// fixture files in `files`.
+pub fn run_fixture(lib_rs: &str, files: &str) -> String {
+    format!(
+        r#"
+//- /Cargo.toml
+[package]
+name = "run_project"
+version = "0.1.0"
+edition = "2021"
+
+//- /src/lib.rs
+pub fn $0run() -> u32 {{
+    1
+}}
+{lib_rs}{files}"#
+    )
+}
+
+/// Returns `find_references` parameters for `run` in [`run_fixture`] with every option
+/// at its default, to be overridden with struct update syntax.
+pub fn references_to_run() -> FindReferencesParams {
+    FindReferencesParams {
+        query: SymbolQuery::Position(PositionParams {
+            file_path: "src/lib.rs".to_string(),
+            line: 1,
+            column: 8,
+            symbol_text: None,
+            occurrence: None,
+            symbol_path: None,
+        }),
+        include_declaration: false,
+        context: LocationContext::default(),
+        summary_only: false,
+        group_file: None,
+        group_item: None,
+        filter: FilterParams::default(),
+        page: PageParams::default(),
+    }
+}
+
+/// Decodes the structured content of a location tool's result.
+pub fn locations_result(result: CallToolResult) -> LocationsResult {
+    serde_json::from_value(result.structured_content.expect("structured content"))
+        .expect("structured content should be a location list")
+}
@@ -16,7 +16,12 @@
 //! ```
 mod common;
 use common::temp_workspace::TestWorkspace;
-use lsp_types::{DocumentSymbolResponse, GotoDefinitionResponse, SymbolKind};
+use kadabra_runes::error::LspError;
+use kadabra_runes::lsp::ext::SyntaxTreeNode;
+use lsp_types::{
+    CompletionItemKind, DocumentHighlightKind, DocumentSymbolResponse, GotoDefinitionResponse,
+    InlayHintKind, SymbolKind,
+};
 
 #[tokio::test]
 async fn goto_definition_add() {
@@ -97,6 +102,44 @@ async fn test_goto_definition() {
     ws.lsp().shutdown().await.expect("Shutdown should succeed");
 }
 
+#[tokio::test]
+async fn test_goto_declaration() {
+    let ws = TestWorkspace::builder()
+        .fixture(&common::comprehensive_fixture())
+        .open_all_files()
+        .build()
+        .await;
+
+    // Go to declaration of `calculate` in the Adder impl (line 11 in calculator.rs)
+    let result = ws
+        .lsp()
+        .goto_declaration(&ws.apath("src/calculator.rs"), 11, 8)
+        .await
+        .expect("goto_declaration should succeed");
+
+    // The declaration is the trait method on line 4, not the impl body
+    let locations = match result {
+        GotoDefinitionResponse::Scalar(location) => vec![location],
+        GotoDefinitionResponse::Array(locations) => locations,
+        GotoDefinitionResponse::Link(links) => links
+            .into_iter()
+            .map(|link| lsp_types::Location {
+                uri: link.target_uri,
+                range: link.target_selection_range,
+            })
+            .collect(),
+    };
+    assert!(
+        locations
+            .iter()
+            .any(|loc| loc.uri.path().ends_with("src/calculator.rs") && loc.range.start.line == 3),
+        "Declaration should be the Calculator trait method, found: {:?}",
+        locations
+    );
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
 #[tokio::test]
 async fn test_find_references() {
     let ws = TestWorkspace::builder()
@@ -362,13 +405,11 @@ async fn test_type_definition() {
         .expect("type_definition should succeed");
 
     match result {
-        GotoDefinitionResponse::Array(locations) => {
-            if !locations.is_empty() {
-                assert!(
-                    locations[0].uri.path().contains("calculator.rs"),
-                    "Type definition should be in calculator.rs"
-                );
-            }
+        GotoDefinitionResponse::Array(locations) if !locations.is_empty() => {
+            assert!(
+                locations[0].uri.path().contains("calculator.rs"),
+                "Type definition should be in calculator.rs"
+            );
         }
         GotoDefinitionResponse::Scalar(location) => {
             assert!(
@@ -382,6 +423,476 @@ async fn test_type_definition() {
     ws.lsp().shutdown().await.expect("Shutdown should succeed");
 }
 
+#[tokio::test]
+async fn test_type_hierarchy() {
+    let ws = TestWorkspace::builder()
+        .fixture(&common::comprehensive_fixture())
+        .open_all_files()
+        .build()
+        .await;
+
+    // Prepare type hierarchy for Calculator trait (line 2)
+    let result = ws
+        .lsp()
+        .prepare_type_hierarchy(&ws.apath("src/calculator.rs"), 2, 16)
+        .await;
+
+    match result {
+        Ok(items) => {
+            // Servers supporting type hierarchy should resolve the trait and its implementors
+            if let Some(item) = items.into_iter().next() {
+                assert_eq!(item.name, "Calculator");
+                let subtypes = ws
+                    .lsp()
+                    .subtypes(item)
+                    .await
+                    .expect("subtypes should succeed");
+                assert!(
+                    subtypes.len() >= 3,
+                    "Calculator should have at least 3 implementors, found {}",
+                    subtypes.len()
+                );
+            }
+        }
+        Err(LspError::CapabilityNotSupported(method)) => {
+            // rust-analyzer does not implement type hierarchy yet; the `type_hierarchy`
+            // tool falls back to declarations (see `test_mcp_type_hierarchy_of_trait`)
+            assert_eq!(method, "textDocument/prepareTypeHierarchy");
+        }
+        Err(e) => panic!("prepare_type_hierarchy failed: {e}"),
+    }
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_document_highlights() {
+    let ws = TestWorkspace::builder()
+        .fixture(&common::comprehensive_fixture())
+        .open_all_files()
+        .build()
+        .await;
+
+    // Highlight the `calc` variable in use_calculator (line 18: "let calc = ...")
+    let result = ws
+        .lsp()
+        .document_highlights(&ws.apath("src/main.rs"), 18, 9)
+        .await
+        .expect("document_highlights should succeed");
+
+    // Declared once and read once by `calc.calculate(15, 25)` on line 19
+    assert_eq!(
+        result.len(),
+        2,
+        "Should find both occurrences of 'calc', found: {:?}",
+        result
+    );
+    assert!(
+        result
+            .iter()
+            .any(|h| h.range.start.line == 18 && h.kind == Some(DocumentHighlightKind::READ)),
+        "Should classify 'calc.calculate' as a read, found: {:?}",
+        result
+    );
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_inlay_hints() {
+    let ws = TestWorkspace::builder()
+        .fixture(&common::comprehensive_fixture())
+        .open_all_files()
+        .build()
+        .await;
+
+    // Hints for `let result = add(x, y);` (line 7 in main.rs)
+    let result = ws
+        .lsp()
+        .inlay_hints(&ws.apath("src/main.rs"), 7, 7)
+        .await
+        .expect("inlay_hints should succeed");
+
+    assert!(
+        result
+            .iter()
+            .any(|h| h.kind == Some(InlayHintKind::TYPE) && h.position.line == 6),
+        "Should have a type hint for 'result', found: {:?}",
+        result
+    );
+    assert!(
+        result
+            .iter()
+            .any(|h| h.kind == Some(InlayHintKind::PARAMETER)),
+        "Should have parameter name hints for 'add(x, y)', found: {:?}",
+        result
+    );
+    assert!(
+        result.iter().all(|h| h.position.line == 6),
+        "Should only return hints inside the requested range, found: {:?}",
+        result
+    );
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_completion() {
+    let ws = TestWorkspace::builder()
+        .fixture(&common::comprehensive_fixture())
+        .open_all_files()
+        .build()
+        .await;
+
+    // Complete after `calc.` in `let sum = calc.calculate(15, 25);` (line 19 in main.rs)
+    let result = ws
+        .lsp()
+        .completion(&ws.apath("src/main.rs"), 19, 20)
+        .await
+        .expect("completion should succeed");
+
+    let calculate = result
+        .iter()
+        .find(|item| item.label == "calculate")
+        .unwrap_or_else(|| {
+            panic!(
+                "Should offer the 'calculate' method, found: {:?}",
+                result.iter().map(|i| &i.label).collect::<Vec<_>>()
+            )
+        });
+    assert_eq!(calculate.kind, Some(CompletionItemKind::METHOD));
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_expand_macro() {
+    let ws = TestWorkspace::builder()
+        .fixture(&common::comprehensive_fixture())
+        .open_all_files()
+        .build()
+        .await;
+
+    // Expand `println!` on line 8 in main.rs
+    let result = ws
+        .lsp()
+        .expand_macro(&ws.apath("src/main.rs"), 8, 7)
+        .await
+        .expect("expand_macro should succeed")
+        .expect("Should find a macro call at the position");
+
+    assert_eq!(result.name, "println!");
+    assert!(
+        result.expansion.contains("format_args"),
+        "Expansion should show the generated code, got: {}",
+        result.expansion
+    );
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_runnables() {
+    let ws = TestWorkspace::builder()
+        .fixture(&common::comprehensive_fixture())
+        .open_all_files()
+        .build()
+        .await;
+
+    let runnables = ws
+        .lsp()
+        .runnables(&ws.apath("src/calculator.rs"))
+        .await
+        .expect("runnables should succeed");
+
+    let test_adder = runnables
+        .iter()
+        .find(|r| r.label.ends_with("test_adder"))
+        .expect("Should list the test_adder test");
+    let command = test_adder.command_line();
+    assert!(
+        command.starts_with("cargo test") && command.contains("test_adder"),
+        "Command line should run the test, got: {command}"
+    );
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_related_tests() {
+    let ws = TestWorkspace::builder()
+        .fixture(&common::comprehensive_fixture())
+        .open_all_files()
+        .build()
+        .await;
+
+    // `Adder` struct on line 8 in calculator.rs is exercised by test_adder
+    let tests = ws
+        .lsp()
+        .related_tests(&ws.apath("src/calculator.rs"), 8, 12)
+        .await
+        .expect("related_tests should succeed");
+
+    assert!(
+        tests
+            .iter()
+            .any(|t| t.runnable.label.ends_with("test_adder")),
+        "Should find test_adder, got: {:?}",
+        tests.iter().map(|t| &t.runnable.label).collect::<Vec<_>>()
+    );
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_ssr() {
+    let ws = TestWorkspace::builder()
+        .fixture(&common::comprehensive_fixture())
+        .open_all_files()
+        .build()
+        .await;
+
+    // Swap the arguments of `add`, called on line 7 in main.rs
+    let edit = ws
+        .lsp()
+        .ssr(
+            "add($a, $b) ==>> add($b, $a)",
+            &ws.apath("src/main.rs"),
+            1,
+            1,
+            Vec::new(),
+        )
+        .await
+        .expect("ssr should succeed");
+
+    let edits: Vec<_> = match edit.document_changes {
+        Some(lsp_types::DocumentChanges::Edits(edits)) => edits
+            .into_iter()
+            .filter(|e| e.text_document.uri.path().ends_with("src/main.rs"))
+            .flat_map(|e| e.edits)
+            .collect(),
+        other => panic!("Expected document edits, got: {other:?}"),
+    };
+    assert!(
+        edits.iter().any(|e| match e {
+            lsp_types::OneOf::Left(edit) =>
+                edit.new_text == "add(y, x)" && edit.range.start.line == 6,
+            lsp_types::OneOf::Right(edit) => edit.text_edit.new_text == "add(y, x)",
+        }),
+        "Should rewrite the call in main.rs, got: {edits:?}"
+    );
+
+    // Invalid rules are reported as errors
+    let result = ws
+        .lsp()
+        .ssr("add($a ==>>", &ws.apath("src/main.rs"), 1, 1, Vec::new())
+        .await;
+    assert!(result.is_err(), "Invalid rule should fail");
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_server_maintenance() {
+    let ws = TestWorkspace::builder()
+        .fixture(&common::comprehensive_fixture())
+        .open_all_files()
+        .build()
+        .await;
+
+    let status = ws
+        .lsp()
+        .analyzer_status(Some(&ws.apath("src/lib.rs")))
+        .await
+        .expect("analyzer_status should succeed");
+    assert!(
+        status.contains("sample_project"),
+        "Status should list the file's crate, got: {status}"
+    );
+
+    ws.lsp()
+        .reload_workspace()
+        .await
+        .expect("reload_workspace should succeed");
+    ws.lsp()
+        .rebuild_proc_macros()
+        .await
+        .expect("rebuild_proc_macros should succeed");
+
+    // Memory profiling is compiled out of release builds of rust-analyzer
+    match ws.lsp().memory_usage().await {
+        Ok(usage) => assert!(!usage.is_empty(), "Memory usage should not be empty"),
+        Err(LspError::RequestFailed(message)) => assert!(
+            message.contains("rust-analyzer/memoryUsage"),
+            "Error should name the request, got: {message}"
+        ),
+        Err(e) => panic!("Unexpected error: {e}"),
+    }
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_crate_graph_and_dependencies() {
+    let ws = TestWorkspace::builder()
+        .fixture(&common::comprehensive_fixture())
+        .open_all_files()
+        .build()
+        .await;
+
+    let graph = ws
+        .lsp()
+        .view_crate_graph(false)
+        .await
+        .expect("view_crate_graph should succeed");
+    assert!(
+        graph.starts_with("digraph") && graph.contains("sample_project"),
+        "Graph should be DOT with the workspace crate, got: {graph}"
+    );
+
+    // The fixture has no registry dependencies; sysroot crates depend on the toolchain
+    ws.lsp()
+        .fetch_dependency_list()
+        .await
+        .expect("fetch_dependency_list should succeed");
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_view_syntax_tree_hir_mir() {
+    let ws = TestWorkspace::builder()
+        .fixture(&common::comprehensive_fixture())
+        .open_all_files()
+        .build()
+        .await;
+
+    let tree = ws
+        .lsp()
+        .view_syntax_tree(&ws.apath("src/lib.rs"))
+        .await
+        .expect("view_syntax_tree should succeed");
+    let root: SyntaxTreeNode = serde_json::from_str(&tree).expect("Syntax tree should be JSON");
+    assert_eq!(root.kind, "SOURCE_FILE");
+    assert!(
+        root.children.iter().any(|child| child.kind == "FN"),
+        "Syntax tree should contain functions"
+    );
+
+    // Body of `add` on line 23 in lib.rs
+    let hir = ws
+        .lsp()
+        .view_hir(&ws.apath("src/lib.rs"), 23, 5)
+        .await
+        .expect("view_hir should succeed");
+    assert!(hir.contains("fn add"), "HIR should show add, got: {hir}");
+
+    let mir = ws
+        .lsp()
+        .view_mir(&ws.apath("src/lib.rs"), 23, 5)
+        .await
+        .expect("view_mir should succeed");
+    assert!(
+        mir.contains("bb0"),
+        "MIR should show basic blocks, got: {mir}"
+    );
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_pull_diagnostics() {
+    let fixture = r#"
+//- /Cargo.toml
+[package]
+name = "pull_project"
+version = "0.1.0"
+edition = "2021"
+
+//- /src/lib.rs
+pub fn compute() -> u32 {
+    let $0value: u32 = "text";
+    value
+}
+"#;
+    let ws = TestWorkspace::builder()
+        .fixture(fixture)
+        .open_all_files()
+        .build()
+        .await;
+    let path = ws.apath("src/lib.rs");
+
+    let diagnostics = ws
+        .lsp()
+        .document_diagnostics(&path)
+        .await
+        .expect("document_diagnostics should succeed");
+    assert!(
+        diagnostics.iter().any(|d| d.range.start.line == 1),
+        "Should report the type mismatch on line 2, got: {diagnostics:?}"
+    );
+
+    // The second pull sends the previous result id and must yield the same diagnostics
+    let again = ws
+        .lsp()
+        .document_diagnostics(&path)
+        .await
+        .expect("second document_diagnostics should succeed");
+    assert_eq!(again, diagnostics);
+
+    // The unified accessors include pulled diagnostics
+    let merged = ws
+        .lsp()
+        .diagnostics(&path)
+        .await
+        .expect("diagnostics should succeed");
+    assert!(diagnostics.iter().all(|d| merged.contains(d)));
+
+    let all = ws
+        .lsp()
+        .all_diagnostics()
+        .await
+        .expect("all_diagnostics should succeed");
+    let uri = lsp_types::Url::from_file_path(&path).unwrap();
+    assert!(
+        all.get(&uri).is_some_and(|d| !d.is_empty()),
+        "all_diagnostics should include lib.rs, got: {all:?}"
+    );
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_external_docs() {
+    let ws = TestWorkspace::builder()
+        .fixture(&common::comprehensive_fixture())
+        .open_all_files()
+        .build()
+        .await;
+
+    // `add` call on line 7 in main.rs
+    let docs = ws
+        .lsp()
+        .external_docs(&ws.apath("src/main.rs"), 7, 18)
+        .await
+        .expect("external_docs should succeed");
+
+    let web = docs.web.expect("Should resolve a docs.rs URL");
+    assert!(
+        web.as_str().ends_with("/sample_project/fn.add.html"),
+        "Web URL should point to add, got: {web}"
+    );
+    let local = docs.local.expect("Should resolve a local docs URL");
+    assert!(
+        local
+            .path()
+            .ends_with("target/doc/sample_project/fn.add.html"),
+        "Local URL should point into target/doc, got: {local}"
+    );
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
 #[tokio::test]
 async fn test_shutdown() {
     let ws = TestWorkspace::builder()
@@ -17,8 +17,13 @@
 mod common;
 
 use common::temp_workspace::TestWorkspace;
+use common::tools::{locations_result, references_to_run};
 use kadabra_runes::mcp::KadabraRunes;
-use kadabra_runes::mcp::tools::PositionParams;
+use kadabra_runes::mcp::tools::{
+    CheckWorkspaceParams, CompletionsParams, ContextMode, DiagnosticsParams, FilterParams,
+    FindReferencesParams, GotoParams, ImplementationsParams, LocationContext, ModuleTreeParams,
+    PageParams, PositionParams, SymbolNameParams, SymbolQuery, TypeHierarchyParams,
+};
 use rmcp::handler::server::wrapper::Parameters;
 use rmcp::model::RawContent;
 
@@ -35,10 +40,16 @@ async fn test_mcp_goto_definition_tool() {
         file_path: ws.root.path().join("src/main.rs").display().to_string(),
         line: 7,
         column: 18,
+        symbol_text: None,
+        occurrence: None,
+        symbol_path: None,
     };
 
     let result = server
-        .goto_definition(Parameters(params))
+        .goto_definition(Parameters(GotoParams {
+            position: params,
+            context: LocationContext::default(),
+        }))
         .await
         .expect("goto_definition tool should succeed");
 
@@ -73,3 +84,1049 @@ async fn test_mcp_goto_definition_tool() {
         text
     );
 }
+
+#[tokio::test]
+async fn test_mcp_module_tree_tool() {
+    let ws = TestWorkspace::builder()
+        .fixture(&common::comprehensive_fixture())
+        .open_all_files()
+        .build()
+        .await;
+    let server = KadabraRunes::new(ws.root.path().into(), ws.lsp());
+    let params = ModuleTreeParams {
+        file_path: ws
+            .root
+            .path()
+            .join("src/calculator.rs")
+            .display()
+            .to_string(),
+        depth: 3,
+    };
+
+    let result = server
+        .module_tree(Parameters(params))
+        .await
+        .expect("module_tree tool should succeed");
+
+    let text = match &result.content[0].raw {
+        RawContent::Text(text_content) => &text_content.text,
+        _ => panic!("Expected Text content, got: {:?}", result.content[0]),
+    };
+
+    // calculator.rs is declared by `pub mod calculator;` in lib.rs
+    assert!(
+        text.contains("Module path: crate::calculator"),
+        "Should show the module path, got: {}",
+        text
+    );
+    assert!(
+        text.contains("lib.rs"),
+        "Should show the crate root, got: {}",
+        text
+    );
+    assert!(
+        text.contains("pub mod calculator - "),
+        "Should show the declaration, got: {}",
+        text
+    );
+    // The inline test module is a submodule of calculator
+    assert!(
+        text.contains("mod tests (inline)"),
+        "Should list the inline tests module, got: {}",
+        text
+    );
+}
+
+#[tokio::test]
+async fn test_mcp_check_workspace_tool() {
+    let fixture = r#"
+//- /Cargo.toml
+[package]
+name = "flycheck_project"
+version = "0.1.0"
+edition = "2021"
+
+//- /src/lib.rs
+pub fn compute() -> i32 {
+    let $0unused = 1;
+    2
+}
+"#;
+    let ws = TestWorkspace::builder()
+        .fixture(fixture)
+        .open_all_files()
+        .build()
+        .await;
+    let server = KadabraRunes::new(ws.root.path().into(), ws.lsp());
+    let params = CheckWorkspaceParams {
+        file_path: None,
+        timeout_secs: 120,
+        max_chars: 20_000,
+    };
+
+    let result = server
+        .check_workspace(Parameters(params))
+        .await
+        .expect("check_workspace tool should succeed");
+
+    let text = match &result.content[0].raw {
+        RawContent::Text(text_content) => &text_content.text,
+        _ => panic!("Expected Text content, got: {:?}", result.content[0]),
+    };
+
+    assert!(
+        text.contains("0 error(s), 1 warning(s) in 1 file(s)"),
+        "Should summarize the warning, got: {}",
+        text
+    );
+    assert!(
+        text.contains("[function] compute"),
+        "Should group by enclosing function, got: {}",
+        text
+    );
+    assert!(
+        text.contains("warning[unused_variables] 2:9"),
+        "Should show the unused variable warning, got: {}",
+        text
+    );
+}
+
+#[tokio::test]
+async fn test_mcp_diagnostics_tool() {
+    let fixture = r#"
+//- /Cargo.toml
+[package]
+name = "diagnostics_project"
+version = "0.1.0"
+edition = "2021"
+
+//- /src/lib.rs
+pub fn compute() -> u32 {
+    let $0value: u32 = "text";
+    value
+}
+"#;
+    let ws = TestWorkspace::builder()
+        .fixture(fixture)
+        .open_all_files()
+        .build()
+        .await;
+    let server = KadabraRunes::new(ws.root.path().into(), ws.lsp());
+    let params = DiagnosticsParams {
+        file_path: Some(ws.apath("src/lib.rs").to_string_lossy().to_string()),
+        max_chars: 20_000,
+    };
+
+    let result = server
+        .diagnostics(Parameters(params))
+        .await
+        .expect("diagnostics tool should succeed");
+
+    let text = match &result.content[0].raw {
+        RawContent::Text(text_content) => &text_content.text,
+        _ => panic!("Expected Text content, got: {:?}", result.content[0]),
+    };
+
+    assert!(
+        text.contains("error(s), 0 warning(s) in 1 file(s)"),
+        "Should summarize the errors, got: {}",
+        text
+    );
+    assert!(
+        text.contains("[function] compute"),
+        "Should group by enclosing function, got: {}",
+        text
+    );
+    assert!(
+        text.contains("error[E0308] 2:22"),
+        "Should show the type mismatch, got: {}",
+        text
+    );
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_mcp_find_references_by_name() {
+    let fixture = r#"
+//- /Cargo.toml
+[package]
+name = "names_project"
+version = "0.1.0"
+edition = "2021"
+
+//- /src/lib.rs
+pub mod alpha;
+pub mod beta;
+
+pub fn call() -> u32 {
+    alpha::run() + beta::run()
+}
+//- /src/alpha.rs
+pub fn $0run() -> u32 {
+    1
+}
+//- /src/beta.rs
+pub fn run() -> u32 {
+    2
+}
+"#;
+    let ws = TestWorkspace::builder()
+        .fixture(fixture)
+        .open_all_files()
+        .build()
+        .await;
+    let server = KadabraRunes::new(ws.root.path().into(), ws.lsp());
+
+    // Two functions named `run` exist, so the bare name is ambiguous
+    let params = FindReferencesParams {
+        query: SymbolQuery::Name(SymbolNameParams {
+            symbol: "run".to_string(),
+            file_path: None,
+        }),
+        include_declaration: false,
+        context: LocationContext::default(),
+        summary_only: false,
+        group_file: None,
+        group_item: None,
+        filter: FilterParams::default(),
+        page: PageParams::default(),
+    };
+    let error = server
+        .find_references(Parameters(params))
+        .await
+        .expect_err("ambiguous name should fail");
+    assert!(
+        error.message.contains("ambiguous; 2 candidates"),
+        "Should list both candidates, got: {}",
+        error.message
+    );
+
+    // Narrowing by file picks alpha::run
+    let params = FindReferencesParams {
+        query: SymbolQuery::Name(SymbolNameParams {
+            symbol: "run".to_string(),
+            file_path: Some("src/alpha.rs".to_string()),
+        }),
+        include_declaration: false,
+        context: LocationContext::default(),
+        summary_only: false,
+        group_file: None,
+        group_item: None,
+        filter: FilterParams::default(),
+        page: PageParams::default(),
+    };
+    let result = server
+        .find_references(Parameters(params))
+        .await
+        .expect("find_references by name should succeed");
+    let text = match &result.content[0].raw {
+        RawContent::Text(text_content) => &text_content.text,
+        _ => panic!("Expected Text content, got: {:?}", result.content[0]),
+    };
+    assert!(
+        text.contains("lib.rs:5"),
+        "Should find the call in lib.rs, got: {}",
+        text
+    );
+
+    // A single fuzzy match is used, and the note says so
+    let params = FindReferencesParams {
+        query: SymbolQuery::Name(SymbolNameParams {
+            symbol: "cal".to_string(),
+            file_path: None,
+        }),
+        include_declaration: true,
+        context: LocationContext::default(),
+        summary_only: false,
+        group_file: None,
+        group_item: None,
+        filter: FilterParams::default(),
+        page: PageParams::default(),
+    };
+    let result = server
+        .find_references(Parameters(params))
+        .await
+        .expect("find_references by a fuzzy name should succeed");
+    let text = match &result.content[0].raw {
+        RawContent::Text(text_content) => &text_content.text,
+        _ => panic!("Expected Text content, got: {:?}", result.content[0]),
+    };
+    assert!(
+        text.starts_with("Note: no exact match for `cal`; using `call`"),
+        "Should name the fuzzy pick, got: {}",
+        text
+    );
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_mcp_implementations_by_name() {
+    let ws = TestWorkspace::builder()
+        .fixture(&common::comprehensive_fixture())
+        .open_all_files()
+        .build()
+        .await;
+    let server = KadabraRunes::new(ws.root.path().into(), ws.lsp());
+
+    // `Calculator` is also re-exported from lib.rs; both resolve to the trait
+    let params = ImplementationsParams {
+        query: SymbolQuery::Name(SymbolNameParams {
+            symbol: "Calculator".to_string(),
+            file_path: None,
+        }),
+        context: LocationContext::default(),
+        filter: FilterParams::default(),
+        page: PageParams::default(),
+    };
+    let result = server
+        .implementations(Parameters(params))
+        .await
+        .expect("implementations by name should succeed");
+    let text = match &result.content[0].raw {
+        RawContent::Text(text_content) => &text_content.text,
+        _ => panic!("Expected Text content, got: {:?}", result.content[0]),
+    };
+    for implementor in ["Adder", "Multiplier", "Subtractor"] {
+        assert!(
+            text.contains(&format!("impl Calculator for {implementor}")),
+            "Should find the impl for {implementor}, got: {}",
+            text
+        );
+    }
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_mcp_type_hierarchy_of_trait() {
+    let ws = TestWorkspace::builder()
+        .fixture(&common::comprehensive_fixture())
+        .open_all_files()
+        .build()
+        .await;
+    let server = KadabraRunes::new(ws.root.path().into(), ws.lsp());
+
+    // Line 2 of calculator.rs: `pub trait Calculator {`
+    let params = TypeHierarchyParams {
+        position: PositionParams {
+            file_path: "src/calculator.rs".to_string(),
+            line: 2,
+            column: 0,
+            symbol_text: Some("Calculator".to_string()),
+            occurrence: None,
+            symbol_path: None,
+        },
+        depth: 2,
+    };
+    let result = server
+        .type_hierarchy(Parameters(params))
+        .await
+        .expect("type_hierarchy should succeed");
+    let text = match &result.content[0].raw {
+        RawContent::Text(text_content) => &text_content.text,
+        _ => panic!("Expected Text content, got: {:?}", result.content[0]),
+    };
+    let (_, subtypes) = text
+        .split_once("Subtypes:")
+        .unwrap_or_else(|| panic!("Should list subtypes, got: {text}"));
+    let implementors = subtypes
+        .lines()
+        .filter(|line| line.contains("Calculator for"));
+    assert!(
+        implementors.count() >= 3,
+        "Should find the Adder, Multiplier and Subtractor impls, got: {text}"
+    );
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_mcp_goto_definition_by_symbol_path() {
+    let fixture = r#"
+//- /Cargo.toml
+[package]
+name = "path-project"
+version = "0.1.0"
+edition = "2021"
+
+//- /src/lib.rs
+pub mod shapes;
+
+pub trait Area {
+    fn area(&self) -> f64;
+}
+
+pub struct Wrapper<T>(pub T);
+
+impl<T: Clone> Wrapper<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+}
+
+pub trait Describe {
+    fn describe(&self) -> String;
+}
+
+impl<T: std::fmt::Debug> Describe for T {
+    fn describe(&self) -> String {
+        format!("{self:?}")
+    }
+}
+//- /src/shapes.rs
+use crate::Area;
+
+pub struct $0Square(pub f64);
+
+impl Square {
+    pub fn new(side: f64) -> Self {
+        Self(side)
+    }
+}
+
+impl Area for Square {
+    fn area(&self) -> f64 {
+        self.0 * self.0
+    }
+}
+"#;
+    let ws = TestWorkspace::builder()
+        .fixture(fixture)
+        .open_all_files()
+        .build()
+        .await;
+    let server = KadabraRunes::new(ws.root.path().into(), ws.lsp());
+    let goto = |symbol_path: &str| {
+        let params = PositionParams {
+            file_path: String::new(),
+            line: 0,
+            column: 0,
+            symbol_text: None,
+            occurrence: None,
+            symbol_path: Some(symbol_path.to_string()),
+        };
+        server.goto_definition(Parameters(GotoParams {
+            position: params,
+            context: LocationContext::default(),
+        }))
+    };
+
+    let cases = [
+        // Trait impl method through the self type
+        ("Square::area", "shapes.rs:12"),
+        // Trait declaration
+        ("crate::Area::area", "lib.rs:4"),
+        // Inherent method of a generic impl, anchored at the crate name
+        ("path_project::Wrapper::new", "lib.rs:10"),
+        // Inherent method preferred; module-qualified
+        ("shapes::Square::new", "shapes.rs:6"),
+        // Blanket impl
+        ("<T as Describe>::describe", "lib.rs:20"),
+        ("<Square as Area>::area", "shapes.rs:12"),
+    ];
+    for (symbol_path, expected) in cases {
+        let result = goto(symbol_path)
+            .await
+            .unwrap_or_else(|e| panic!("{symbol_path} should resolve: {}", e.message));
+        let text = match &result.content[0].raw {
+            RawContent::Text(text_content) => &text_content.text,
+            _ => panic!("Expected Text content, got: {:?}", result.content[0]),
+        };
+        assert!(
+            text.contains(expected),
+            "{symbol_path} should resolve to {expected}, got: {}",
+            text
+        );
+    }
+
+    let error = goto("Square::perimeter")
+        .await
+        .expect_err("unknown path should fail");
+    assert!(
+        error.message.contains("not found"),
+        "Should report the path as not found, got: {}",
+        error.message
+    );
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_mcp_goto_definition_token_anchored() {
+    let ws = TestWorkspace::builder()
+        .fixture(&common::comprehensive_fixture())
+        .open_all_files()
+        .build()
+        .await;
+    let server = KadabraRunes::new(ws.root.path().into(), ws.lsp());
+    let main_rs = ws.root.path().join("src/main.rs").display().to_string();
+
+    // Line 7 of main.rs: `    let result = add(x, y);`
+    let params = PositionParams {
+        file_path: main_rs.clone(),
+        line: 7,
+        column: 0,
+        symbol_text: Some("add".to_string()),
+        occurrence: None,
+        symbol_path: None,
+    };
+    let result = server
+        .goto_definition(Parameters(GotoParams {
+            position: params,
+            context: LocationContext::default(),
+        }))
+        .await
+        .expect("goto_definition by token should succeed");
+    let text = match &result.content[0].raw {
+        RawContent::Text(text_content) => &text_content.text,
+        _ => panic!("Expected Text content, got: {:?}", result.content[0]),
+    };
+    assert!(
+        text.contains("lib.rs:22"),
+        "Should find add in lib.rs, got: {}",
+        text
+    );
+
+    // Column 17 is the space before `add`
+    let params = PositionParams {
+        file_path: main_rs,
+        line: 7,
+        column: 17,
+        symbol_text: None,
+        occurrence: None,
+        symbol_path: None,
+    };
+    let result = server
+        .goto_definition(Parameters(GotoParams {
+            position: params,
+            context: LocationContext::default(),
+        }))
+        .await
+        .expect("goto_definition on whitespace should succeed");
+    let text = match &result.content[0].raw {
+        RawContent::Text(text_content) => &text_content.text,
+        _ => panic!("Expected Text content, got: {:?}", result.content[0]),
+    };
+    assert!(
+        text.starts_with(
+            "Note: column 17 on line 7 is not on an identifier; snapped to `add` at column 18"
+        ),
+        "Should report the snapped identifier, got: {}",
+        text
+    );
+    assert!(
+        text.contains("lib.rs:22"),
+        "Should find add in lib.rs, got: {}",
+        text
+    );
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_mcp_goto_definition_structured_output() {
+    let ws = TestWorkspace::builder()
+        .fixture(&common::comprehensive_fixture())
+        .open_all_files()
+        .build()
+        .await;
+    let server = KadabraRunes::new(ws.root.path().into(), ws.lsp());
+    let params = PositionParams {
+        file_path: ws.root.path().join("src/main.rs").display().to_string(),
+        line: 7,
+        column: 18,
+        symbol_text: None,
+        occurrence: None,
+        symbol_path: None,
+    };
+
+    let result = server
+        .goto_definition(Parameters(GotoParams {
+            position: params,
+            context: LocationContext::default(),
+        }))
+        .await
+        .expect("goto_definition tool should succeed");
+
+    // The text rendering stays available as a fallback
+    let RawContent::Text(text) = &result.content[0].raw else {
+        panic!("Expected Text content, got: {:?}", result.content[0]);
+    };
+    assert!(text.text.contains("pub fn add"), "got: {}", text.text);
+
+    let output = locations_result(result);
+    let [location] = output.locations.as_slice() else {
+        panic!("Expected one location, got: {:?}", output.locations);
+    };
+    assert!(location.file_path.ends_with("lib.rs"), "got: {location:?}");
+    assert_eq!((location.line, location.column), (22, 8));
+    assert_eq!(location.context, "pub fn add(a: i32, b: i32) -> i32 {");
+    assert_eq!(
+        location.context_after.as_deref(),
+        Some(&["    a + b".to_string(), "}".to_string()][..])
+    );
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_mcp_goto_definition_relative_paths() {
+    let ws = TestWorkspace::builder()
+        .fixture(&common::comprehensive_fixture())
+        .open_all_files()
+        .build()
+        .await;
+    let server = KadabraRunes::new(ws.root.path().into(), ws.lsp());
+    let params = PositionParams {
+        file_path: "src/main.rs".to_string(),
+        line: 7,
+        column: 18,
+        symbol_text: None,
+        occurrence: None,
+        symbol_path: None,
+    };
+
+    let result = server
+        .goto_definition(Parameters(GotoParams {
+            position: params,
+            context: LocationContext::Lines(0),
+        }))
+        .await
+        .expect("goto_definition with a relative path should succeed");
+
+    let RawContent::Text(text) = &result.content[0].raw else {
+        panic!("Expected Text content, got: {:?}", result.content[0]);
+    };
+    assert!(
+        text.text.starts_with("src/lib.rs:22:8"),
+        "Should render the workspace-relative path, got: {}",
+        text.text
+    );
+    let output = locations_result(result);
+    assert_eq!(output.locations[0].file_path, "src/lib.rs");
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_mcp_goto_definition_rejects_bad_paths() {
+    let ws = TestWorkspace::builder()
+        .fixture(&common::comprehensive_fixture())
+        .open_all_files()
+        .build()
+        .await;
+    let server = KadabraRunes::new(ws.root.path().into(), ws.lsp());
+    let goto = |file_path: &str| {
+        server.goto_definition(Parameters(GotoParams {
+            position: PositionParams {
+                file_path: file_path.to_string(),
+                line: 1,
+                column: 1,
+                symbol_text: None,
+                occurrence: None,
+                symbol_path: None,
+            },
+            context: LocationContext::default(),
+        }))
+    };
+
+    let error = goto("/etc/shadow")
+        .await
+        .expect_err("paths outside the workspace should be refused");
+    assert!(
+        error.message.contains("outside the workspace"),
+        "got: {}",
+        error.message
+    );
+
+    let error = goto("src/mian.rs")
+        .await
+        .expect_err("missing files should be reported");
+    assert!(
+        error
+            .message
+            .starts_with("file not found: src/mian.rs; did you mean")
+            && error.message.contains("src/main.rs"),
+        "got: {}",
+        error.message
+    );
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_mcp_find_references_paginated() {
+    let fixture = common::tools::run_fixture(
+        r#"
+pub mod other;
+
+pub fn twice() -> u32 {
+    run() + run()
+}
+"#,
+        r#"//- /src/other.rs
+pub fn once() -> u32 {
+    crate::run()
+}
+"#,
+    );
+    let ws = TestWorkspace::builder()
+        .fixture(&fixture)
+        .open_all_files()
+        .build()
+        .await;
+    let server = KadabraRunes::new(ws.root.path().into(), ws.lsp());
+    let find_references = |page: PageParams| {
+        server.find_references(Parameters(FindReferencesParams {
+            page,
+            ..references_to_run()
+        }))
+    };
+
+    let result = find_references(PageParams {
+        limit: Some(2),
+        ..PageParams::default()
+    })
+    .await
+    .expect("find_references should succeed");
+    let RawContent::Text(text) = &result.content[0].raw else {
+        panic!("Expected Text content, got: {:?}", result.content[0]);
+    };
+    assert!(
+        text.text.starts_with(
+            "3 references in 2 files\nShowing references 1-2 of 3; next page: cursor \"offset:2\""
+        ),
+        "Should summarize the truncated page, got: {}",
+        text.text
+    );
+    assert!(text.text.contains("lib.rs (2)"), "got: {}", text.text);
+
+    let output = locations_result(result);
+    assert_eq!(output.locations.len(), 2);
+    let page = output.page.expect("find_references should report its page");
+    assert_eq!((page.total, page.returned), (3, 2));
+    let cursor = page.next_cursor.expect("a next page should remain");
+
+    // The cursor fetches the remaining reference
+    let result = find_references(PageParams {
+        cursor: Some(cursor),
+        ..PageParams::default()
+    })
+    .await
+    .expect("find_references with cursor should succeed");
+    let output = locations_result(result);
+    assert_eq!(output.locations.len(), 1);
+    let page = output.page.expect("find_references should report its page");
+    assert_eq!((page.offset, page.returned, page.next_cursor), (2, 1, None));
+
+    // A tight character budget moves results to later pages
+    let result = find_references(PageParams {
+        max_output_chars: 1,
+        ..PageParams::default()
+    })
+    .await
+    .expect("find_references with a budget should succeed");
+    let output = locations_result(result);
+    assert_eq!(output.locations.len(), 1);
+    assert_eq!(
+        output.page.and_then(|page| page.next_cursor).as_deref(),
+        Some("offset:1")
+    );
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_mcp_find_references_enclosing_context() {
+    let fixture = common::tools::run_fixture(
+        r#"
+pub fn handle_request(
+    id: u32,
+) -> u32 {
+    let base = id * 2;
+    base + run()
+}
+"#,
+        "",
+    );
+    let ws = TestWorkspace::builder()
+        .fixture(&fixture)
+        .open_all_files()
+        .build()
+        .await;
+    let server = KadabraRunes::new(ws.root.path().into(), ws.lsp());
+    let find_references = |context: LocationContext| {
+        server.find_references(Parameters(FindReferencesParams {
+            context,
+            ..references_to_run()
+        }))
+    };
+
+    let result = find_references(LocationContext::Mode(ContextMode::Enclosing))
+        .await
+        .expect("find_references with enclosing context should succeed");
+    let RawContent::Text(text) = &result.content[0].raw else {
+        panic!("Expected Text content, got: {:?}", result.content[0]);
+    };
+    assert!(
+        text.text
+            .contains("inside `fn handle_request` (lines 5\u{2013}10)"),
+        "Should name the enclosing function, got: {}",
+        text.text
+    );
+    assert!(text.text.contains(") -> u32 {"), "got: {}", text.text);
+    assert!(
+        !text.text.contains("let base"),
+        "Should not show lines around the reference, got: {}",
+        text.text
+    );
+
+    let output = locations_result(result);
+    let item = output.locations[0]
+        .enclosing
+        .as_ref()
+        .expect("the reference should have an enclosing item");
+    assert_eq!(item.signature_line, 5);
+
+    // Without context only the location header is rendered
+    let result = find_references(LocationContext::Mode(ContextMode::None))
+        .await
+        .expect("find_references without context should succeed");
+    let RawContent::Text(text) = &result.content[0].raw else {
+        panic!("Expected Text content, got: {:?}", result.content[0]);
+    };
+    assert!(
+        text.text.trim_end().ends_with("lib.rs:9:12"),
+        "got: {}",
+        text.text
+    );
+    assert!(!text.text.contains("run()"), "got: {}", text.text);
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_mcp_find_references_grouped() {
+    let fixture = common::tools::run_fixture(
+        r#"
+pub struct Server;
+
+impl Server {
+    pub fn handle(&self) -> u32 {
+        run() + run()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn it_runs() {
+        assert_eq!(super::run(), 1);
+    }
+}
+"#,
+        "",
+    );
+    let ws = TestWorkspace::builder()
+        .fixture(&fixture)
+        .open_all_files()
+        .build()
+        .await;
+    let server = KadabraRunes::new(ws.root.path().into(), ws.lsp());
+
+    let result = server
+        .find_references(Parameters(FindReferencesParams {
+            summary_only: true,
+            ..references_to_run()
+        }))
+        .await
+        .expect("find_references summary should succeed");
+    let RawContent::Text(text) = &result.content[0].raw else {
+        panic!("Expected Text content, got: {:?}", result.content[0]);
+    };
+    assert!(
+        text.text.starts_with("3 references in 1 file; 1 in tests"),
+        "Should start with the counts, got: {}",
+        text.text
+    );
+    assert!(
+        text.text.contains("  impl Server > fn handle (2)"),
+        "got: {}",
+        text.text
+    );
+    assert!(
+        text.text.contains("  mod tests > fn it_runs (1) [test]"),
+        "got: {}",
+        text.text
+    );
+    let output = locations_result(result);
+    assert!(output.locations.is_empty());
+    let summary = output.summary.expect("find_references should summarize");
+    assert_eq!((summary.total, summary.in_tests), (3, 1));
+
+    // Expanding the test group lists only its reference
+    let result = server
+        .find_references(Parameters(FindReferencesParams {
+            group_item: Some("it_runs".to_string()),
+            ..references_to_run()
+        }))
+        .await
+        .expect("find_references for a group should succeed");
+    let output = locations_result(result);
+    assert_eq!(output.locations.len(), 1);
+    assert_eq!(output.locations[0].line, 17);
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_mcp_find_references_filtered() {
+    let fixture = common::tools::run_fixture(
+        r#"
+pub mod other;
+
+#[cfg(test)]
+mod checks {
+    #[test]
+    fn it_runs() {
+        assert_eq!(super::run(), 1);
+    }
+}
+
+// rust-analyzer leaves `cfg(not(test))` code inactive, so negate a compound predicate
+#[cfg(not(all(test, miri)))]
+pub fn production() -> u32 {
+    run()
+}
+
+#[doc(alias = "test")]
+pub fn latest() -> u32 {
+    run()
+}
+"#,
+        r#"//- /src/other.rs
+#[cfg(test)]
+mod tests;
+
+pub fn once() -> u32 {
+    crate::run()
+}
+//- /src/other/tests.rs
+fn helper() -> u32 {
+    crate::run()
+}
+//- /tests/it.rs
+#[test]
+fn it_runs_externally() {
+    assert_eq!(run_project::run(), 1);
+}
+"#,
+    );
+    let ws = TestWorkspace::builder()
+        .fixture(&fixture)
+        .open_all_files()
+        .build()
+        .await;
+    let server = KadabraRunes::new(ws.root.path().into(), ws.lsp());
+    let total = async |filter: FilterParams| {
+        let result = server
+            .find_references(Parameters(FindReferencesParams {
+                summary_only: true,
+                filter,
+                ..references_to_run()
+            }))
+            .await
+            .expect("find_references should succeed");
+        locations_result(result)
+            .summary
+            .map_or(0, |summary| summary.total)
+    };
+
+    assert_eq!(total(FilterParams::default()).await, 6);
+
+    // The #[cfg(test)] module, its out-of-line tests.rs and the tests/ directory are
+    // test code; the cfg(not(..)) and doc attributes are not
+    let without_tests = FilterParams {
+        include_tests: false,
+        ..FilterParams::default()
+    };
+    assert_eq!(total(without_tests).await, 3);
+
+    let excluded = FilterParams {
+        exclude_paths: vec!["src/other.rs".to_string()],
+        ..FilterParams::default()
+    };
+    assert_eq!(total(excluded).await, 5);
+
+    let included = FilterParams {
+        include_paths: vec!["tests/**".to_string()],
+        ..FilterParams::default()
+    };
+    assert_eq!(total(included).await, 1);
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}
+
+#[tokio::test]
+async fn test_mcp_completions_after_dot() {
+    let fixture = r#"
+//- /Cargo.toml
+[package]
+name = "completion_project"
+version = "0.1.0"
+edition = "2021"
+
+//- /src/lib.rs
+pub struct Counter {
+    pub count: u32,
+}
+
+impl Counter {
+    pub fn bump(&mut self) {}
+}
+
+pub fn use_counter(counter: Counter) {
+    counter.$0
+}
+"#;
+    let ws = TestWorkspace::builder()
+        .fixture(fixture)
+        .open_all_files()
+        .build()
+        .await;
+    let server = KadabraRunes::new(ws.root.path().into(), ws.lsp());
+
+    // Column 13 is just after `counter.`, at the end of the line
+    let params = CompletionsParams {
+        position: PositionParams {
+            file_path: "src/lib.rs".to_string(),
+            line: 10,
+            column: 13,
+            symbol_text: None,
+            occurrence: None,
+            symbol_path: None,
+        },
+        max_results: 50,
+    };
+    let result = server
+        .completions(Parameters(params))
+        .await
+        .expect("completions should succeed");
+    let RawContent::Text(text) = &result.content[0].raw else {
+        panic!("Expected Text content, got: {:?}", result.content[0]);
+    };
+    assert!(
+        text.text.contains("] bump") && text.text.contains("] count"),
+        "Should list the members of Counter, got: {}",
+        text.text
+    );
+    assert!(
+        !text.text.contains("snapped"),
+        "Should keep the cursor after the dot, got: {}",
+        text.text
+    );
+
+    ws.lsp().shutdown().await.expect("Shutdown should succeed");
+}